@@ -0,0 +1,28 @@
+#![no_main]
+// Parts of the replay module the fuzz target does not call
+#![allow(dead_code)]
+
+use libfuzzer_sys::fuzz_target;
+
+// The launcher is not a library, so the replay parser and its error type are compiled into the
+// fuzz target from their source files, with the error at the module path the parser uses
+#[path = "../../src/config/error/replay_error.rs"]
+pub mod replay_error;
+
+mod config {
+    pub mod error {
+        pub use crate::replay_error;
+    }
+}
+
+#[path = "../../src/replay/mod.rs"]
+mod replay;
+
+// Parsing any bytes returns an error instead of panicking or allocating without bound
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = replay::Replay::parse(data) {
+        let _ = replay.played_at();
+        let _ = replay::mods::acronyms(replay.mods);
+        let _ = replay.mode.to_string();
+    }
+});
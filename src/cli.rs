@@ -0,0 +1,88 @@
+use clap::{Args, Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Launch with the default settings when the config file cannot be loaded, instead of exiting
+    #[arg(long, global = true)]
+    pub fallback_defaults: bool,
+
+    /// The profile to launch with, overriding the config's default_profile
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Print what would be downloaded and launched, without downloading or launching anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the dry run as JSON
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
+
+    /// The config file to use instead of the one next to the launcher's executable
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Create the config file from the given options without prompting, for scripted setups
+    Init(InitArgs),
+    /// Print what is in an osu! replay (.osr) file
+    Replay(ReplayArgs),
+    /// Read and change the launcher's config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// The directory osu! is installed in
+    #[arg(long)]
+    pub osu_path: Option<String>,
+
+    /// Applications to enable, like `danser,otd`
+    #[arg(long, value_delimiter = ',')]
+    pub enable: Vec<String>,
+
+    /// Turn on `download` for the enabled applications, the launcher downloads them when it starts
+    #[arg(long)]
+    pub download: bool,
+
+    /// Replace the config file if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// The replay file
+    pub path: String,
+
+    /// Print the replay as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print every setting with its effective value and where the value came from
+    List,
+    /// Print the effective value of a setting, like `danser.settings_name`
+    Get { setting: String },
+    /// Change a setting in the config file, like `open_tablet_driver.enabled true`
+    Set { setting: String, value: String },
+    /// Reset an application's settings in the config file to the defaults
+    Reset { app: String },
+    /// Print the JSON Schema of the config file
+    Schema,
+    /// Enable an application in the config file
+    Enable { app: String },
+    /// Disable an application in the config file
+    Disable { app: String },
+}
@@ -0,0 +1,205 @@
+use std::process::ExitCode;
+
+use serde_json::Value;
+
+use crate::{
+    cli::ConfigCommand,
+    config::{
+        data::ConfigData,
+        error::config_error::ConfigError,
+        manager::{ConfigLayerKind, LauncherConfig},
+        schema::{config_schema, is_known_setting},
+    },
+    util::json::get_path,
+};
+
+pub fn run(command: &ConfigCommand, launcher_config: &mut LauncherConfig) -> ExitCode {
+    match command {
+        ConfigCommand::List => list(launcher_config),
+        ConfigCommand::Get { setting } => get(launcher_config, setting),
+        ConfigCommand::Set { setting, value } => set(launcher_config, setting, value),
+        ConfigCommand::Reset { app } => reset(launcher_config, app),
+        ConfigCommand::Schema => schema(),
+        ConfigCommand::Enable { app } => set_enabled(launcher_config, app, true),
+        ConfigCommand::Disable { app } => set_enabled(launcher_config, app, false),
+    }
+}
+
+fn list(launcher_config: &LauncherConfig) -> ExitCode {
+    for (path, value, layer_name) in launcher_config.settings() {
+        println!("{} = {}  ({})", path, value, layer_name);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn get(launcher_config: &LauncherConfig, setting: &str) -> ExitCode {
+    let path: Vec<&str> = setting.split('.').collect();
+
+    match launcher_config.get(&path) {
+        // Print strings without quotes so the output can be used in scripts
+        Some(Value::String(value)) => println!("{}", value),
+        Some(value) => println!("{}", value),
+        None => {
+            eprintln!("Setting {} not found", setting);
+
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn set(launcher_config: &mut LauncherConfig, setting: &str, value: &str) -> ExitCode {
+    let path: Vec<&str> = setting.split('.').collect();
+
+    if !is_known_setting(&path) {
+        eprintln!("Setting {} not found", setting);
+
+        return ExitCode::FAILURE;
+    }
+
+    // Values are JSON, but strings do not need quotes, so a value that does not fit the setting is
+    // tried again as a string
+    let as_string = Value::String(value.to_string());
+    let result = match serde_json::from_str(value) {
+        Ok(json_value) => launcher_config
+            .set(&path, json_value)
+            .or_else(|error| launcher_config.set(&path, as_string).map_err(|_| error)),
+        Err(_) => launcher_config.set(&path, as_string),
+    };
+
+    save(
+        launcher_config,
+        result,
+        &format!("Set {} to {}", setting, value),
+        setting,
+    )
+}
+
+fn reset(launcher_config: &mut LauncherConfig, app: &str) -> ExitCode {
+    let defaults = ConfigData::new();
+    let is_built_in_app = defaults
+        .applications()
+        .iter()
+        .any(|application| application.get_name() == app);
+
+    let app_defaults = serde_json::to_value(&defaults)
+        .unwrap_or_else(|e| panic!("Error creating config data: {}", e))
+        .get(app)
+        .filter(|_| is_built_in_app)
+        .cloned();
+
+    let Some(app_defaults) = app_defaults else {
+        eprintln!("Application {} has no defaults to reset to", app);
+
+        return ExitCode::FAILURE;
+    };
+
+    let result = launcher_config.set(&[app], app_defaults);
+
+    save(
+        launcher_config,
+        result,
+        &format!("Reset {} to the defaults", app),
+        app,
+    )
+}
+
+/// Saves the local config file, then tells where else the settings under `changed` are set: the
+/// user config file, which the local file now overrides, and the layers that still decide some of
+/// them, like an environment variable, which wins over the file.
+fn save(
+    launcher_config: &LauncherConfig,
+    result: Result<(), ConfigError>,
+    message: &str,
+    changed: &str,
+) -> ExitCode {
+    match result.and_then(|_| launcher_config.save()) {
+        Ok(_) => {
+            println!("{} in {}", message, launcher_config.file_path);
+
+            let changed_path: Vec<&str> = changed.split('.').collect();
+
+            for layer in &launcher_config.layers {
+                if layer.kind == ConfigLayerKind::UserFile
+                    && get_path(&layer.document, &changed_path).is_some()
+                {
+                    println!(
+                        "The local config file now overrides {} from the {}",
+                        changed, layer.name
+                    );
+                }
+            }
+
+            for (path, value, _) in launcher_config.settings() {
+                if path != changed && !path.starts_with(&format!("{}.", changed)) {
+                    continue;
+                }
+
+                let segments: Vec<&str> = path.split('.').collect();
+                let source = launcher_config.setting_source(&segments).filter(|layer| {
+                    !matches!(
+                        layer.kind,
+                        ConfigLayerKind::Defaults | ConfigLayerKind::LocalFile
+                    )
+                });
+
+                if let Some(layer) = source {
+                    println!("{} is still {}, as the {} sets it", path, value, layer.name);
+                }
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn schema() -> ExitCode {
+    let schema = serde_json::to_string_pretty(&config_schema())
+        .unwrap_or_else(|e| panic!("Error creating config schema: {}", e));
+
+    println!("{}", schema);
+
+    ExitCode::SUCCESS
+}
+
+fn set_enabled(launcher_config: &mut LauncherConfig, app: &str, enabled: bool) -> ExitCode {
+    let app_exists = launcher_config
+        .config
+        .applications()
+        .iter()
+        .any(|application| application.get_name() == app);
+
+    if !app_exists {
+        eprintln!("Application {} not found", app);
+
+        return ExitCode::FAILURE;
+    }
+
+    let result = launcher_config.update(|config| {
+        for application in config.applications_mut() {
+            if application.get_name() == app {
+                application.set_enabled(enabled);
+            }
+        }
+    });
+
+    let message = format!("{} {}", if enabled { "Enabled" } else { "Disabled" }, app);
+    let is_built_in_app = ConfigData::new()
+        .applications()
+        .iter()
+        .any(|application| application.get_name() == app);
+    let setting = if is_built_in_app {
+        format!("{}.enabled", app)
+    } else {
+        format!("custom_apps.{}.enabled", app)
+    };
+
+    save(launcher_config, result, &message, &setting)
+}
@@ -0,0 +1,88 @@
+use std::{path::Path, process::ExitCode};
+
+use crate::{
+    cli::InitArgs,
+    config::{data::ConfigData, manager::LauncherConfig},
+    util::file::file_exists,
+};
+
+// Short names accepted by --enable, next to the names used in the config file
+const APP_ALIASES: [(&str, &str); 3] = [
+    ("otd", "open_tablet_driver"),
+    ("opentabletdriver", "open_tablet_driver"),
+    ("trainer", "osu_trainer"),
+];
+
+pub fn run(args: &InitArgs, file_path: &str) -> ExitCode {
+    if file_exists(file_path) && !args.force {
+        eprintln!(
+            "Config file {} already exists, use --force to replace it",
+            file_path
+        );
+
+        return ExitCode::FAILURE;
+    }
+
+    let config = match build_config(args) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}", message);
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut launcher_config = LauncherConfig::with_defaults(file_path);
+
+    launcher_config.config = config;
+
+    match launcher_config.create_config_file() {
+        Ok(_) => {
+            println!("Created config file {}", file_path);
+
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn build_config(args: &InitArgs) -> Result<ConfigData, String> {
+    let mut config = ConfigData::new();
+
+    if let Some(osu_path) = &args.osu_path {
+        config.osu.path = Some(osu_path.clone());
+        config.danser.osu_replays_path = Some(
+            Path::new(osu_path)
+                .join("Replays")
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+
+    for app in &args.enable {
+        let app = APP_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(app))
+            .map_or(app.as_str(), |(_, name)| name);
+
+        let application = config
+            .applications_mut()
+            .into_iter()
+            .find(|application| application.get_name() == app)
+            .ok_or_else(|| format!("Application {} not found", app))?;
+
+        application.set_enabled(true);
+    }
+
+    if args.download {
+        config.danser.download = config.danser.enabled;
+        config.open_tablet_driver.download = config.open_tablet_driver.enabled;
+        config.osu_trainer.download = config.osu_trainer.enabled;
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,94 @@
+use std::{path::Path, process::ExitCode};
+
+use serde_json::json;
+
+use crate::{
+    cli::ReplayArgs,
+    replay::{mods::acronyms, Replay},
+};
+
+pub fn run(args: &ReplayArgs) -> ExitCode {
+    let replay = match Replay::read(Path::new(&args.path)) {
+        Ok(replay) => replay,
+        Err(error) => {
+            eprintln!("{}: {}", args.path, error);
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mods = acronyms(replay.mods);
+    let played_at = replay.played_at().map(|played_at| played_at.to_rfc3339());
+
+    if args.json {
+        let info = json!({
+            "mode": replay.mode,
+            "version": replay.version,
+            "beatmap_md5": replay.beatmap_md5,
+            "player": replay.player,
+            "replay_md5": replay.replay_md5,
+            "count_300": replay.count_300,
+            "count_100": replay.count_100,
+            "count_50": replay.count_50,
+            "count_geki": replay.count_geki,
+            "count_katu": replay.count_katu,
+            "count_miss": replay.count_miss,
+            "score": replay.score,
+            "max_combo": replay.max_combo,
+            "perfect": replay.perfect,
+            "mods": mods,
+            "mods_bits": replay.mods,
+            "life_bar": replay.life_bar,
+            "timestamp": played_at,
+            "replay_data_length": replay.replay_data.len(),
+            "online_score_id": replay.online_score_id,
+            "target_practice_accuracy": replay.target_practice_accuracy,
+        });
+
+        println!("{}", serde_json::to_string_pretty(&info).unwrap());
+
+        return ExitCode::SUCCESS;
+    }
+
+    println!("Player: {}", replay.player);
+    println!("Mode: {}", replay.mode);
+    println!("Beatmap MD5: {}", replay.beatmap_md5);
+    println!("Replay MD5: {}", replay.replay_md5);
+    println!("osu! version: {}", replay.version);
+    println!(
+        "Played at: {}",
+        played_at.as_deref().unwrap_or("unknown date")
+    );
+    println!("Score: {}", replay.score);
+    println!(
+        "Max combo: {}{}",
+        replay.max_combo,
+        if replay.perfect { " (full combo)" } else { "" }
+    );
+    println!(
+        "Hits: {} x300, {} x100, {} x50, {} geki, {} katu, {} misses",
+        replay.count_300,
+        replay.count_100,
+        replay.count_50,
+        replay.count_geki,
+        replay.count_katu,
+        replay.count_miss
+    );
+
+    if mods.is_empty() {
+        println!("Mods: none");
+    } else {
+        println!("Mods: {}", mods.join(" "));
+    }
+
+    if let Some(accuracy) = replay.target_practice_accuracy {
+        println!("Target practice accuracy: {}", accuracy);
+    }
+
+    match replay.online_score_id {
+        0 => println!("Online score ID: not submitted"),
+        id => println!("Online score ID: {}", id),
+    }
+
+    ExitCode::SUCCESS
+}
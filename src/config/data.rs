@@ -1,30 +1,127 @@
 use std::{
-    env,
+    collections::BTreeMap,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU32},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
-use super::{error::app_process_error::AppProcessError, traits::app_data::Application};
-use crate::process::try_spawn_danser_process;
+use super::{
+    error::{app_data_error::AppDataError, app_process_error::AppProcessError},
+    migration::CURRENT_VERSION,
+    traits::app_data::Application,
+};
+use crate::{
+    process::try_spawn_danser_process,
+    replay::GameMode,
+    util::path::{expand_path, launcher_dir},
+};
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 pub type AppJoinHandle = JoinHandle<Result<std::process::ExitStatus, AppProcessError>>;
 
-#[derive(Serialize, Deserialize, Clone)]
+/// What the placeholders in applications' `args`, `env` and `cwd` are replaced with, and how
+/// their executables are run.
+#[derive(Clone)]
+pub struct LaunchContext {
+    /// The expanded path of osu!, if it could be expanded.
+    pub osu_path: Option<String>,
+    pub launcher_dir: String,
+    /// The global runner, for applications without their own.
+    pub runner: Runner,
+}
+
+impl LaunchContext {
+    pub fn new(config: &ConfigData) -> LaunchContext {
+        LaunchContext {
+            osu_path: config.osu.get_expanded_path().ok(),
+            launcher_dir: launcher_dir().to_string_lossy().into_owned(),
+            runner: config.runner.clone(),
+        }
+    }
+}
+
+/// An application's `args`, `env` and `cwd` with the placeholders in them replaced.
+#[derive(Serialize)]
+pub struct ResolvedLaunchOptions {
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+}
+
+/// A started application.
+pub struct AppProcess {
+    pub name: String,
+    pub state: Arc<ProcessState>,
+    pub handle: AppJoinHandle,
+}
+
+/// What the launcher knows about a started application, shared with the task watching it.
+#[derive(Default)]
+pub struct ProcessState {
+    /// The id of the application's current process, which changes when it is restarted. 0 when
+    /// there is no process, like for the Danser replay watcher the launcher runs itself.
+    pub pid: AtomicU32,
+    /// Cancelled to ask the application to stop, and not restart it.
+    pub stop: CancellationToken,
+    pub restarts: AtomicU32,
+    /// Set when the application kept crashing and was not restarted again.
+    pub crash_looping: AtomicBool,
+    /// Set when the launcher did not start the application, but watches a copy that was already
+    /// running.
+    pub attached: AtomicBool,
+    /// Processes the application had the launcher start for it, like Danser renders, which are
+    /// stopped along with it.
+    pub helper_pids: Mutex<Vec<u32>>,
+}
+
+/// The launcher's config, stored in `launcher_config.json`.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ConfigData {
+    /// The JSON Schema describing this file, for autocompletion and validation in editors.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    /// The version of this config file. It is upgraded automatically by the launcher.
+    pub version: u64,
+    /// Osu! itself.
     pub osu: OsuData,
+    /// Rewind, to watch for new replays.
     pub rewind: RewindData,
+    /// Danser, to render new replays to videos.
     pub danser: DanserData,
+    /// The OpenTabletDriver daemon.
     pub open_tablet_driver: OpenTabletDriverData,
+    /// FunOrange's osu-trainer.
     pub osu_trainer: OsuTrainerData,
+    /// Other applications to launch alongside osu!.
+    #[serde(default)]
+    pub custom_apps: Vec<CustomAppData>,
+    /// What to do with the other applications when osu! exits.
+    #[serde(default)]
+    pub shutdown: ShutdownOptions,
+    /// How to run the applications' executables, unless an application sets its own `runner`.
+    #[serde(default)]
+    pub runner: Runner,
+    /// The profile to use when `--profile` is not given.
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    /// Named sets of overrides for any of the settings above, picked with `--profile`.
+    #[serde(default)]
+    pub profiles: Map<String, Value>,
 }
 
 impl ConfigData {
     pub fn new() -> ConfigData {
-        let app_data_location = ConfigData::get_app_data_location();
-
-        // Generate sensible defaults
+        // Generate sensible defaults, paths are expanded when they are used so the config can be
+        // shared between machines
+        let app_data_location = "${LOCALAPPDATA}";
         let osu_path = Path::new(&app_data_location)
             .join("osu!")
             .to_string_lossy()
@@ -62,25 +159,42 @@ impl ConfigData {
 
         // This is what will be written to the config file
         ConfigData {
+            schema: None,
+            version: CURRENT_VERSION,
             osu: OsuData {
                 enabled: true,
                 path: Some(osu_path),
                 executable_name: Some("osu!.exe".to_string()),
+                launch: LaunchOptions {
+                    depends_on: vec!["open_tablet_driver".to_string()],
+                    // Keep a copy of osu! that is already open as the anchor for closing the rest
+                    if_running: IfRunning::Attach,
+                    ..LaunchOptions::default()
+                },
             },
             rewind: RewindData {
                 enabled: false,
                 path: Some(rewind_path),
                 executable_name: Some("Rewind.exe".to_string()),
+                launch: LaunchOptions {
+                    depends_on: vec!["osu".to_string()],
+                    ..LaunchOptions::default()
+                },
             },
             danser: DanserData {
                 enabled: false,
                 path: Some(danser_path),
                 executable_name: Some("danser-cli.exe".to_string()),
                 settings_name: Some("default".to_string()),
+                output_template: default_output_template(),
                 osu_replays_path: Some(replays_path),
+                trigger: RenderTrigger::default(),
+                render: RenderOptions::default(),
+                settle: SettleOptions::default(),
                 source: Some(
                     "https://github.com/Wieku/danser-go/releases/download/0.9.1/danser-0.9.1-win.zip".to_string()),
                 download: false,
+                launch: LaunchOptions::default(),
             },
             open_tablet_driver: OpenTabletDriverData {
                 enabled: false,
@@ -88,6 +202,11 @@ impl ConfigData {
                 executable_name: Some("OpenTabletDriver.Daemon.exe".to_string()),
                 source: Some("https://github.com/OpenTabletDriver/OpenTabletDriver/releases/download/v0.6.3.0/OpenTabletDriver.win-x64.zip".to_string()),
                 download: false,
+                launch: LaunchOptions {
+                    // Give the daemon time to find the tablet before osu! starts
+                    ready: Some(ReadyCondition::Delay(2.0)),
+                    ..LaunchOptions::default()
+                },
             },
             osu_trainer: OsuTrainerData {
                 enabled: false,
@@ -97,37 +216,78 @@ impl ConfigData {
                     "https://github.com/FunOrange/osu-trainer/releases/download/1.7.0/osu-trainer-v1.7.0.zip"
                 .to_string()),
                 download: false,
+                launch: LaunchOptions {
+                    depends_on: vec!["osu".to_string()],
+                    ..LaunchOptions::default()
+                },
             },
+            custom_apps: vec![],
+            shutdown: ShutdownOptions::default(),
+            runner: Runner::default(),
+            default_profile: None,
+            profiles: Map::new(),
         }
     }
 
-    pub fn get_app_data_location() -> String {
-        let local_app_data = env::var("LOCALAPPDATA");
+    pub fn applications(&self) -> Vec<&dyn Application> {
+        let mut applications: Vec<&dyn Application> = vec![
+            &self.osu,
+            &self.rewind,
+            &self.danser,
+            &self.open_tablet_driver,
+            &self.osu_trainer,
+        ];
+
+        for custom_app in &self.custom_apps {
+            applications.push(custom_app);
+        }
 
-        match local_app_data {
-            Ok(path) => path,
-            Err(e) => {
-                println!("Error getting local app data: {}", e);
+        applications
+    }
 
-                return "".to_string();
-            }
+    pub fn applications_mut(&mut self) -> Vec<&mut dyn Application> {
+        let mut applications: Vec<&mut dyn Application> = vec![
+            &mut self.osu,
+            &mut self.rewind,
+            &mut self.danser,
+            &mut self.open_tablet_driver,
+            &mut self.osu_trainer,
+        ];
+
+        for custom_app in &mut self.custom_apps {
+            applications.push(custom_app);
         }
+
+        applications
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OsuData {
+    /// The directory where the application's files live.
     pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
     pub executable_name: Option<String>,
+    /// Whether to launch the application.
     pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
 }
 
 #[async_trait]
 impl Application for OsuData {
+    fn get_name(&self) -> String {
+        "osu".to_string()
+    }
+
     fn get_enabled(&self) -> bool {
         self.enabled
     }
 
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     fn get_path(&self) -> Option<String> {
         self.path.clone()
     }
@@ -135,21 +295,38 @@ impl Application for OsuData {
     fn get_executable_name(&self) -> Option<String> {
         self.executable_name.clone()
     }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct RewindData {
+    /// The directory where the application's files live.
     pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
     pub executable_name: Option<String>,
+    /// Whether to launch the application.
     pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
 }
 
 #[async_trait]
 impl Application for RewindData {
+    fn get_name(&self) -> String {
+        "rewind".to_string()
+    }
+
     fn get_enabled(&self) -> bool {
         self.enabled
     }
 
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     fn get_path(&self) -> Option<String> {
         self.path.clone()
     }
@@ -157,25 +334,59 @@ impl Application for RewindData {
     fn get_executable_name(&self) -> Option<String> {
         self.executable_name.clone()
     }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct DanserData {
+    /// The directory where the application's files live.
     pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
     pub executable_name: Option<String>,
+    /// The name of the Danser settings to render with.
     pub settings_name: Option<String>,
+    /// The name of rendered videos, without the extension. `{player}`, `{mods}`, `{accuracy}`,
+    /// `{date}`, `{beatmap_md5}` and `{replay_stem}` are replaced with the replay's.
+    #[serde(default = "default_output_template")]
+    pub output_template: String,
+    /// The directory to watch for new osu! replays.
     pub osu_replays_path: Option<String>,
+    /// Which new replays to render.
+    #[serde(default)]
+    pub trigger: RenderTrigger,
+    /// How many replays are rendered at once, and for how long.
+    #[serde(default)]
+    pub render: RenderOptions,
+    /// How to tell that osu! has finished writing a new replay.
+    #[serde(default)]
+    pub settle: SettleOptions,
+    /// Whether to download the application from `source` when `path` does not exist yet.
     pub download: bool,
+    /// The URL of a zip of the application, extracted to `path` when it is downloaded.
     pub source: Option<String>,
+    /// Whether to launch the application.
     pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
 }
 
 #[async_trait]
 impl Application for DanserData {
+    fn get_name(&self) -> String {
+        "danser".to_string()
+    }
+
     fn get_enabled(&self) -> bool {
         self.enabled
     }
 
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     fn get_path(&self) -> Option<String> {
         self.path.clone()
     }
@@ -188,34 +399,172 @@ impl Application for DanserData {
         self.source.clone()
     }
 
-    fn try_spawn_process(&self) -> Result<AppJoinHandle, AppProcessError> {
-        try_spawn_danser_process(self)
+    fn get_download(&self) -> bool {
+        self.download
+    }
+
+    fn try_spawn_process(&self, context: &LaunchContext) -> Result<AppProcess, AppProcessError> {
+        try_spawn_danser_process(self, context)
+    }
+
+    // The launcher runs the replay watcher itself, a running danser-cli is only a render
+    fn get_running_executable(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
     }
 }
 
+fn default_output_template() -> String {
+    "{replay_stem}".to_string()
+}
+
 impl DanserData {
-    pub fn get_replays_path(&self) -> Option<PathBuf> {
-        let replays_path = self.osu_replays_path.clone()?;
+    pub fn get_replays_path(&self) -> Result<PathBuf, AppDataError> {
+        let replays_path = self
+            .osu_replays_path
+            .clone()
+            .ok_or(AppDataError::PathNotFound)?;
+
+        expand_path(&replays_path)
+            .map(PathBuf::from)
+            .map_err(AppDataError::PathExpansionFailed)
+    }
+}
+
+/// Which new replays Danser renders.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RenderTrigger {
+    /// Render every new replay.
+    Always,
+    /// Render no replays.
+    Never,
+    /// Render a new replay when a key is held as it is saved. Only works on Windows.
+    Hotkey {
+        /// The key, like `R`, `F5` or a virtual key code like `0x52`.
+        key: String,
+    },
+    /// Render new replays while a file exists.
+    MarkerFile { path: String },
+    /// Render the new replays that match the filter.
+    Filter(ReplayFilter),
+}
 
-        Some(PathBuf::from(replays_path))
+impl Default for RenderTrigger {
+    fn default() -> RenderTrigger {
+        RenderTrigger::Hotkey {
+            key: "R".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct RenderOptions {
+    /// How many replays to render at the same time. Other replays wait in a queue.
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+    /// How many seconds a render can take before it is considered stuck and killed. 0 for no
+    /// limit.
+    #[serde(default = "default_render_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            max_parallel: default_max_parallel(),
+            timeout: default_render_timeout(),
+        }
+    }
+}
+
+fn default_max_parallel() -> usize {
+    1
+}
+
+fn default_render_timeout() -> u64 {
+    1800
+}
+
+/// A new replay is rendered once its size has stopped changing and it reads as a whole replay.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct SettleOptions {
+    /// How many seconds the replay's size has to stay the same.
+    #[serde(
+        default = "default_settle_interval",
+        deserialize_with = "deserialize_seconds"
+    )]
+    #[schemars(range(min = 0))]
+    pub interval: f64,
+    /// How many seconds to wait for the replay to be written before giving up on it.
+    #[serde(default = "default_settle_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for SettleOptions {
+    fn default() -> SettleOptions {
+        SettleOptions {
+            interval: default_settle_interval(),
+            timeout: default_settle_timeout(),
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+fn default_settle_interval() -> f64 {
+    1.0
+}
+
+fn default_settle_timeout() -> u64 {
+    60
+}
+
+/// Which replays to render, by what was played. Empty lists match every replay.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct ReplayFilter {
+    /// Render replays of any of these game modes.
+    #[serde(default)]
+    pub modes: Vec<GameMode>,
+    /// Render replays played with all of these mods, like `HD` or `DT`.
+    #[serde(default)]
+    pub mods: Vec<String>,
+    /// Render replays of any of these players, in any case.
+    #[serde(default)]
+    pub players: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OpenTabletDriverData {
+    /// The directory where the application's files live.
     pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
     pub executable_name: Option<String>,
+    /// The URL of a zip of the application, extracted to `path` when it is downloaded.
     pub source: Option<String>,
+    /// Whether to download the application from `source` when `path` does not exist yet.
     pub download: bool,
+    /// Whether to launch the application.
     pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
 }
 
 #[async_trait]
 impl Application for OpenTabletDriverData {
+    fn get_name(&self) -> String {
+        "open_tablet_driver".to_string()
+    }
+
     fn get_enabled(&self) -> bool {
         self.enabled
     }
 
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     fn get_path(&self) -> Option<String> {
         self.path.clone()
     }
@@ -227,23 +576,46 @@ impl Application for OpenTabletDriverData {
     fn get_public_download_url(&self) -> Option<String> {
         self.source.clone()
     }
+
+    fn get_download(&self) -> bool {
+        self.download
+    }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OsuTrainerData {
+    /// The directory where the application's files live.
     pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
     pub executable_name: Option<String>,
+    /// Whether to download the application from `source` when `path` does not exist yet.
     pub download: bool,
+    /// The URL of a zip of the application, extracted to `path` when it is downloaded.
     pub source: Option<String>,
+    /// Whether to launch the application.
     pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
 }
 
 #[async_trait]
 impl Application for OsuTrainerData {
+    fn get_name(&self) -> String {
+        "osu_trainer".to_string()
+    }
+
     fn get_enabled(&self) -> bool {
         self.enabled
     }
 
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
     fn get_path(&self) -> Option<String> {
         self.path.clone()
     }
@@ -255,4 +627,308 @@ impl Application for OsuTrainerData {
     fn get_public_download_url(&self) -> Option<String> {
         self.source.clone()
     }
+
+    fn get_download(&self) -> bool {
+        self.download
+    }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
+    }
+}
+
+/// How an application is started, shared by every application.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct LaunchOptions {
+    /// Arguments passed to the executable.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables set for the executable.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// The working directory to launch the executable from. Defaults to the launcher's working
+    /// directory.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Applications that must be running and ready before this one starts. Disabled applications
+    /// are ignored.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How to tell that the application is ready for the applications that depend on it. Without
+    /// it, the application is ready as soon as it starts.
+    #[serde(default)]
+    pub ready: Option<ReadyCondition>,
+    /// How many seconds to wait for the application to become ready before giving up.
+    #[serde(default = "default_ready_timeout")]
+    pub ready_timeout: u64,
+    /// When to start the application again after it exits by itself.
+    #[serde(default)]
+    pub restart: RestartOptions,
+    /// Where the application's output goes.
+    #[serde(default)]
+    pub log: LogOptions,
+    /// What to do when the application is already running when the launcher starts.
+    #[serde(default)]
+    pub if_running: IfRunning,
+    /// How to run the executable. Defaults to the global `runner`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<Runner>,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> LaunchOptions {
+        LaunchOptions {
+            args: vec![],
+            env: BTreeMap::new(),
+            cwd: None,
+            depends_on: vec![],
+            ready: None,
+            ready_timeout: default_ready_timeout(),
+            restart: RestartOptions::default(),
+            log: LogOptions::default(),
+            if_running: IfRunning::default(),
+            runner: None,
+        }
+    }
+}
+
+fn default_ready_timeout() -> u64 {
+    30
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct RestartOptions {
+    #[serde(default)]
+    pub policy: RestartPolicy,
+    /// How many times in a row to restart the application before giving up on it.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// How many seconds to wait before the first restart. The wait doubles with every restart in a
+    /// row, up to a minute.
+    #[serde(default = "default_backoff", deserialize_with = "deserialize_seconds")]
+    #[schemars(range(min = 0))]
+    pub backoff: f64,
+    /// How many seconds the application has to run for before its restarts stop counting as in a
+    /// row.
+    #[serde(default = "default_reset_after")]
+    pub reset_after: u64,
+}
+
+impl Default for RestartOptions {
+    fn default() -> RestartOptions {
+        RestartOptions {
+            policy: RestartPolicy::default(),
+            max_attempts: default_max_attempts(),
+            backoff: default_backoff(),
+            reset_after: default_reset_after(),
+        }
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_backoff() -> f64 {
+    1.0
+}
+
+// Seconds are waited for as a Duration, which cannot be negative or longer than u64::MAX seconds
+fn deserialize_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let seconds = f64::deserialize(deserializer)?;
+
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        D::Error::custom(format!(
+            "{} is not a number of seconds, it must be at least 0 and below {}",
+            seconds,
+            u64::MAX
+        ))
+    })?;
+
+    Ok(seconds)
+}
+
+fn default_reset_after() -> u64 {
+    60
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IfRunning {
+    /// Leave the running copy alone and do not start another one.
+    #[default]
+    Skip,
+    /// Do not start another copy, but close the running one with the other applications.
+    Attach,
+    /// Start another copy anyway.
+    LaunchAnyway,
+}
+
+/// The application's output is written to `logs/<name>.log` next to the launcher.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct LogOptions {
+    /// How many megabytes the log file can grow to before it is moved to `<name>.1.log`.
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// How many old log files to keep.
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+    /// Whether to start every line with the time it was written.
+    #[serde(default)]
+    pub timestamps: bool,
+    /// Whether to also show the output on the launcher's console, prefixed with `[name]`.
+    #[serde(default)]
+    pub console: bool,
+}
+
+impl Default for LogOptions {
+    fn default() -> LogOptions {
+        LogOptions {
+            max_size_mb: default_max_size_mb(),
+            max_files: default_max_files(),
+            timestamps: false,
+            console: false,
+        }
+    }
+}
+
+fn default_max_size_mb() -> u64 {
+    10
+}
+
+fn default_max_files() -> u32 {
+    3
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Never restart the application.
+    #[default]
+    Never,
+    /// Restart the application when it exits with an error.
+    OnFailure,
+    /// Restart the application whenever it exits.
+    Always,
+}
+
+/// How an application's executable is run.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Runner {
+    /// Run the executable directly.
+    #[default]
+    Native,
+    /// Run the executable with Wine, for the Windows applications on Linux and macOS.
+    Wine {
+        /// The Wine binary to use. Defaults to `wine` from the `PATH`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        binary: Option<String>,
+        /// The Wine prefix to run the executable in, set as `WINEPREFIX`. Defaults to Wine's own.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        prefix: Option<String>,
+    },
+    /// Run the executable with another command, like Proton or a sandbox. The executable's path is
+    /// passed after `args`.
+    Custom {
+        /// The command to run.
+        command: String,
+        /// Arguments passed to the command before the executable's path.
+        #[serde(default)]
+        args: Vec<String>,
+        /// Extra environment variables set for the command, like Proton's
+        /// `STEAM_COMPAT_DATA_PATH`.
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadyCondition {
+    /// Ready after waiting this many seconds.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    Delay(#[schemars(range(min = 0))] f64),
+    /// Ready once this file exists.
+    File(String),
+    /// Ready once this TCP port on this machine accepts connections.
+    Port(u16),
+    /// Ready once a process with this executable name is running, like `osu!.exe`.
+    Process(String),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ShutdownOptions {
+    /// The application whose exit stops all the others, by its config name or custom app name.
+    /// Without it, the launcher waits for every application to exit by itself.
+    #[serde(default = "default_anchor")]
+    pub anchor: Option<String>,
+    /// How many seconds to give each application to close after asking it to, before it is killed.
+    #[serde(default = "default_shutdown_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for ShutdownOptions {
+    fn default() -> ShutdownOptions {
+        ShutdownOptions {
+            anchor: default_anchor(),
+            timeout: default_shutdown_timeout(),
+        }
+    }
+}
+
+fn default_anchor() -> Option<String> {
+    Some("osu".to_string())
+}
+
+fn default_shutdown_timeout() -> u64 {
+    10
+}
+
+/// An application launched alongside osu!, configured by the user.
+#[derive(Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CustomAppData {
+    /// A name for the application, used in the launcher's messages and to pick it in profiles.
+    pub name: String,
+    /// The directory where the application's files live.
+    pub path: Option<String>,
+    /// The executable to launch, relative to `path`.
+    pub executable_name: Option<String>,
+    /// The URL of a zip of the application, downloaded and extracted to `path` when `path` does not exist yet.
+    pub source: Option<String>,
+    /// Whether to launch the application.
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub launch: LaunchOptions,
+}
+
+#[async_trait]
+impl Application for CustomAppData {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn get_path(&self) -> Option<String> {
+        self.path.clone()
+    }
+
+    fn get_executable_name(&self) -> Option<String> {
+        self.executable_name.clone()
+    }
+
+    fn get_public_download_url(&self) -> Option<String> {
+        self.source.clone()
+    }
+
+    fn get_launch_options(&self) -> &LaunchOptions {
+        &self.launch
+    }
 }
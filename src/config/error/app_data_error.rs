@@ -5,6 +5,7 @@ pub enum AppDataError {
     DownloadDisabled,
     DownloadFailed,
     DownloadUrlNotFound,
+    PathExpansionFailed(String),
 }
 
 impl std::fmt::Display for AppDataError {
@@ -15,6 +16,7 @@ impl std::fmt::Display for AppDataError {
             AppDataError::DownloadDisabled => write!(f, "Download for this app is disabled."),
             AppDataError::DownloadFailed => write!(f, "Download failed."),
             AppDataError::DownloadUrlNotFound => write!(f, "Download URL not found."),
+            AppDataError::PathExpansionFailed(message) => write!(f, "{}.", message),
         }
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AppProcessError {
     AppWaitError,
     AppLaunchError(String),
@@ -0,0 +1,78 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    ReadError {
+        file: String,
+        error: std::io::Error,
+    },
+    WriteError {
+        file: String,
+        error: std::io::Error,
+    },
+    ParseError {
+        origin: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        path: Option<String>,
+        message: String,
+    },
+    MigrationError {
+        file: String,
+        message: String,
+    },
+    ProfileNotFound {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::ReadError { file, error } => {
+                write!(f, "Failed to read config file {}: {}", file, error)
+            }
+            ConfigError::WriteError { file, error } => {
+                write!(f, "Failed to write config file {}: {}", file, error)
+            }
+            ConfigError::ParseError {
+                origin,
+                line,
+                column,
+                path,
+                message,
+            } => {
+                write!(f, "Invalid config in {}", origin)?;
+
+                if let (Some(line), Some(column)) = (line, column) {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+
+                if let Some(path) = path {
+                    write!(f, " ({})", path)?;
+                }
+
+                write!(f, ": {}", message)
+            }
+            ConfigError::MigrationError { file, message } => {
+                write!(f, "Failed to upgrade config file {}: {}", file, message)
+            }
+            ConfigError::ProfileNotFound { name, available } if available.is_empty() => {
+                write!(
+                    f,
+                    "Profile \"{}\" not found, no profiles are configured",
+                    name
+                )
+            }
+            ConfigError::ProfileNotFound { name, available } => write!(
+                f,
+                "Profile \"{}\" not found, available profiles: {}",
+                name,
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,26 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LaunchError {
+    UnknownDependency { app: String, dependency: String },
+    DependencyCycle(Vec<String>),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LaunchError::UnknownDependency { app, dependency } => write!(
+                f,
+                "{} depends on {}, which is not a configured application",
+                app, dependency
+            ),
+            LaunchError::DependencyCycle(apps) => write!(
+                f,
+                "Applications depend on each other in a cycle: {}",
+                apps.join(" -> ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
@@ -1,2 +1,5 @@
 pub mod app_data_error;
 pub mod app_process_error;
+pub mod config_error;
+pub mod launch_error;
+pub mod replay_error;
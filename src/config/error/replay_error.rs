@@ -0,0 +1,30 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    ReadFailed(String),
+    UnexpectedEnd { position: usize },
+    InvalidGameMode(u8),
+    InvalidString { position: usize },
+    InvalidLength { position: usize },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::ReadFailed(msg) => write!(f, "Failed to read replay: {}", msg),
+            ReplayError::UnexpectedEnd { position } => {
+                write!(f, "Replay ends unexpectedly at byte {}", position)
+            }
+            ReplayError::InvalidGameMode(mode) => write!(f, "Unknown game mode {} in replay", mode),
+            ReplayError::InvalidString { position } => {
+                write!(f, "Invalid text in replay at byte {}", position)
+            }
+            ReplayError::InvalidLength { position } => {
+                write!(f, "Invalid length in replay at byte {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
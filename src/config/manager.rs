@@ -1,72 +1,1093 @@
+use std::collections::HashSet;
+use std::env;
 use std::fs;
 use std::path::Path;
 
 use crate::config::data::ConfigData;
-use crate::util::file::file_exists;
-use serde_json;
+use crate::config::error::config_error::ConfigError;
+use crate::config::migration::{document_version, migrate, CURRENT_VERSION};
+use crate::config::schema::{config_schema, is_known_setting_in, SCHEMA_FILE_NAME};
+use crate::util::file::{file_exists, rotate_backups, write_atomically};
+use crate::util::json::{flatten, get_path, locate, merge_overrides, set_path};
+use crate::util::path::launcher_dir;
+use serde_json::{self, Map, Value};
+
+/// Environment variables starting with this override single settings, like
+/// `OSU_LAUNCHER__DANSER__ENABLED=true`.
+pub const ENV_PREFIX: &str = "OSU_LAUNCHER__";
+
+/// How many previous versions of the config file `save` keeps.
+const BACKUP_COUNT: usize = 3;
+
+/// Where a layer of the config came from, from lowest to highest precedence.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigLayerKind {
+    Defaults,
+    UserFile,
+    LocalFile,
+    Profile,
+    Environment,
+}
 
 #[derive(Clone)]
-pub struct LauncherConfig<'a> {
-    pub file_path: &'a str,
+pub struct ConfigLayer {
+    pub kind: ConfigLayerKind,
+    pub name: String,
+    pub document: Value,
+    text: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct LauncherConfig {
+    pub file_path: String,
     pub config: ConfigData,
+    pub layers: Vec<ConfigLayer>,
+}
+
+impl TryFrom<&str> for LauncherConfig {
+    type Error = ConfigError;
+
+    /// Loads the config from its layers: the defaults, the user's config file, the local config
+    /// file at `file_path` and `OSU_LAUNCHER__` environment variables. On first launch, when
+    /// neither file exists, the local config file is created with the defaults. Files from an
+    /// older version of the launcher are upgraded once the config has loaded.
+    fn try_from(file_path: &str) -> Result<LauncherConfig, ConfigError> {
+        LauncherConfig::load(file_path, true)
+    }
 }
 
-impl<'a> From<&'a str> for LauncherConfig<'a> {
-    fn from(file_path: &'a str) -> LauncherConfig<'a> {
+impl LauncherConfig {
+    /// Loads the config like `try_from`, without creating or upgrading any file, so looking at the
+    /// config leaves the files as they were.
+    pub fn read_only(file_path: &str) -> Result<LauncherConfig, ConfigError> {
+        LauncherConfig::load(file_path, false)
+    }
+
+    fn load(file_path: &str, write_files: bool) -> Result<LauncherConfig, ConfigError> {
+        let mut launcher_config = LauncherConfig::with_defaults(file_path);
+
+        if write_files && LauncherConfig::is_first_launch(file_path) {
+            launcher_config.create_config_file()?;
+        }
+
+        let mut file_paths = vec![];
+
+        if let Some(user_file_path) = LauncherConfig::user_config_file_path() {
+            if file_exists(&user_file_path) {
+                file_paths.push((ConfigLayerKind::UserFile, user_file_path));
+            }
+        }
+
         if LauncherConfig::config_file_exists(file_path) {
-            let current_config_file_data = LauncherConfig::read_config_file(file_path);
+            file_paths.push((ConfigLayerKind::LocalFile, file_path.to_string()));
+        }
 
-            return match current_config_file_data {
-                Ok(data) => LauncherConfig {
-                    file_path: file_path,
-                    config: data.clone(),
-                },
-                Err(message) => {
-                    println!("{}", message);
+        let mut upgrades = vec![];
+
+        for (kind, file_path) in file_paths {
+            let (layer, original_document) = LauncherConfig::read_config_file(kind, &file_path)?;
+
+            if let Some(original_document) = original_document.filter(|_| write_files) {
+                upgrades.push((launcher_config.layers.len(), file_path, original_document));
+            }
+
+            launcher_config.layers.push(layer);
+        }
+
+        let file_document = launcher_config.merged_document();
+
+        launcher_config
+            .layers
+            .extend(LauncherConfig::environment_layers(
+                &file_document,
+                env::vars(),
+            ));
+
+        launcher_config.config = launcher_config.merge_layers()?;
 
-                    return LauncherConfig {
-                        file_path: file_path,
-                        config: ConfigData::new(),
-                    };
+        // Only a config that loads is written back, so a file with a mistake in it is left as it
+        // was for the user to fix
+        for (position, file_path, original_document) in upgrades {
+            let layer = &mut launcher_config.layers[position];
+
+            layer.text = Some(LauncherConfig::write_upgraded_config_file(
+                &file_path,
+                &original_document,
+                &layer.document,
+            )?);
+        }
+
+        Ok(launcher_config)
+    }
+
+    pub fn with_defaults(file_path: &str) -> LauncherConfig {
+        let config = ConfigData::new();
+        let defaults = ConfigLayer {
+            kind: ConfigLayerKind::Defaults,
+            name: "defaults".to_string(),
+            document: serde_json::to_value(&config)
+                .unwrap_or_else(|e| panic!("Error creating config data: {}", e)),
+            text: None,
+        };
+
+        LauncherConfig {
+            file_path: file_path.to_string(),
+            config,
+            layers: vec![defaults],
+        }
+    }
+
+    /// The config file next to the launcher's executable, so the launcher behaves the same no
+    /// matter which directory it is started from.
+    pub fn local_config_file_path() -> String {
+        launcher_dir()
+            .join("launcher_config.json")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// The config file shared by every copy of the launcher for the current user.
+    pub fn user_config_file_path() -> Option<String> {
+        let config_dir = dirs::config_dir()?;
+
+        Some(
+            config_dir
+                .join("osu-launcher-rs")
+                .join("launcher_config.json")
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    pub fn is_first_launch(file_path: &str) -> bool {
+        let user_file_exists = LauncherConfig::user_config_file_path()
+            .is_some_and(|user_file_path| file_exists(&user_file_path));
+
+        !LauncherConfig::config_file_exists(file_path) && !user_file_exists
+    }
+
+    /// Applies the named profile's overrides to the loaded config, or the config's
+    /// `default_profile` when no name is given. Returns the name of the applied profile.
+    pub fn apply_profile(
+        &mut self,
+        profile_name: Option<&str>,
+    ) -> Result<Option<String>, ConfigError> {
+        let profile_name = match profile_name.or(self.config.default_profile.as_deref()) {
+            Some(profile_name) => profile_name.to_string(),
+            None => return Ok(None),
+        };
+
+        let profile = self.config.profiles.get(&profile_name).ok_or_else(|| {
+            ConfigError::ProfileNotFound {
+                name: profile_name.clone(),
+                available: self.config.profiles.keys().cloned().collect(),
+            }
+        })?;
+
+        // Environment variables still win over the profile
+        let position = self
+            .layers
+            .iter()
+            .position(|layer| layer.kind > ConfigLayerKind::Profile)
+            .unwrap_or(self.layers.len());
+
+        self.layers.insert(
+            position,
+            ConfigLayer {
+                kind: ConfigLayerKind::Profile,
+                name: format!("profile {}", profile_name),
+                document: profile.clone(),
+                text: None,
+            },
+        );
+
+        self.config = self.merge_layers()?;
+
+        Ok(Some(profile_name))
+    }
+
+    /// Every setting of the effective config as a dotted path, with its value and the name of the
+    /// layer it came from.
+    pub fn settings(&self) -> Vec<(String, Value, String)> {
+        let document = serde_json::to_value(&self.config)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+        flatten(&document)
+            .into_iter()
+            .map(|(path, value)| {
+                let segments: Vec<&str> = path.split('.').collect();
+                let layer_name = self
+                    .setting_source(&segments)
+                    .map_or("defaults".to_string(), |layer| layer.name.clone());
+
+                (path, value.clone(), layer_name)
+            })
+            .collect()
+    }
+
+    /// The highest layer that sets a setting, which is the one its effective value comes from.
+    pub fn setting_source(&self, path: &[&str]) -> Option<&ConfigLayer> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|layer| get_path(&layer.document, path).is_some())
+    }
+
+    fn merged_document(&self) -> Value {
+        let mut document = Value::Object(Map::new());
+
+        for layer in &self.layers {
+            merge_overrides(&mut document, &layer.document);
+        }
+
+        document
+    }
+
+    fn merge_layers(&self) -> Result<ConfigData, ConfigError> {
+        // Custom apps with the same name are merged together, so two of them in one layer can
+        // only be told apart before merging
+        for layer in &self.layers {
+            LauncherConfig::check_custom_app_names(layer)?;
+        }
+
+        LauncherConfig::check_unknown_settings(&self.layers)?;
+
+        let document = self.merged_document();
+
+        serde_path_to_error::deserialize(document.clone())
+            .map_err(|error| self.invalid_setting_error(&document, error))
+    }
+
+    // Unknown settings, like a misspelled `enabled`, would otherwise be ignored without a word.
+    // The app structs flatten their launch options, which rules out serde's deny_unknown_fields
+    fn check_unknown_settings(layers: &[ConfigLayer]) -> Result<(), ConfigError> {
+        let schema = config_schema();
+
+        for layer in layers {
+            if layer.kind == ConfigLayerKind::Defaults {
+                continue;
+            }
+
+            let unknown_setting = flatten(&layer.document)
+                .into_iter()
+                .map(|(path, _)| path)
+                .find(|path| {
+                    !path.is_empty()
+                        && !is_known_setting_in(&schema, &path.split('.').collect::<Vec<_>>())
+                });
+
+            let Some(path) = unknown_setting else {
+                continue;
+            };
+
+            let segments: Vec<&str> = path.split('.').collect();
+            let location = layer
+                .text
+                .as_deref()
+                .and_then(|text| locate(text, &segments));
+
+            return Err(ConfigError::ParseError {
+                origin: layer.name.clone(),
+                line: location.map(|(line, _)| line),
+                column: location.map(|(_, column)| column),
+                path: Some(path.clone()),
+                message: "unknown setting, check its spelling".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // Custom apps are looked up by name, in `depends_on` and the shutdown anchor, so their names
+    // cannot be used twice or be one of a built-in application's. Names are compared ignoring
+    // case, as environment variables pick custom apps that way
+    fn check_custom_app_names(layer: &ConfigLayer) -> Result<(), ConfigError> {
+        let Some(custom_apps) = layer.document.get("custom_apps").and_then(Value::as_array) else {
+            return Ok(());
+        };
+
+        let built_in_names: Vec<String> = ConfigData::new()
+            .applications()
+            .iter()
+            .map(|application| application.get_name())
+            .collect();
+        let mut names = HashSet::new();
+
+        for (index, custom_app) in custom_apps.iter().enumerate() {
+            let Some(name) = custom_app.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+
+            let message = if built_in_names
+                .iter()
+                .any(|built_in_name| built_in_name.eq_ignore_ascii_case(name))
+            {
+                format!(
+                    "\"{}\" is the name of a built-in application, give the custom app another name",
+                    name
+                )
+            } else if !names.insert(name.to_lowercase()) {
+                format!("another custom app is already named \"{}\"", name)
+            } else {
+                continue;
+            };
+
+            let index = index.to_string();
+            let location = layer
+                .text
+                .as_deref()
+                .and_then(|text| locate(text, &["custom_apps", &index, "name"]));
+
+            return Err(ConfigError::ParseError {
+                origin: layer.name.clone(),
+                line: location.map(|(line, _)| line),
+                column: location.map(|(_, column)| column),
+                path: Some(format!("custom_apps.{}.name", index)),
+                message,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Blames the highest layer that sets the invalid setting, or its closest parent
+    fn invalid_setting_error(
+        &self,
+        document: &Value,
+        error: serde_path_to_error::Error<serde_json::Error>,
+    ) -> ConfigError {
+        let mut segments: Vec<String> = vec![];
+
+        for segment in error.path().iter() {
+            let segment = match segment {
+                serde_path_to_error::Segment::Seq { index } => {
+                    let path: Vec<&str> = segments.iter().map(String::as_str).collect();
+                    let item = get_path(document, &path).and_then(|list| list.get(index));
+
+                    match item
+                        .and_then(|item| item.get("name"))
+                        .and_then(Value::as_str)
+                    {
+                        Some(name) => name.to_string(),
+                        None => index.to_string(),
+                    }
                 }
+                segment => segment.to_string(),
             };
+
+            segments.push(segment);
         }
 
-        let config = LauncherConfig {
-            file_path: file_path,
-            config: ConfigData::new(),
+        let path: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let blame = (0..=path.len()).rev().find_map(|length| {
+            let layer = self
+                .layers
+                .iter()
+                .rev()
+                .find(|layer| get_path(&layer.document, &path[..length]).is_some())?;
+
+            Some((layer, &path[..length]))
+        });
+
+        let (origin, location) = match blame {
+            Some((layer, located_path)) => (
+                layer.name.clone(),
+                layer
+                    .text
+                    .as_deref()
+                    .and_then(|text| locate(text, located_path)),
+            ),
+            None => ("defaults".to_string(), None),
         };
 
-        config.create_config_file();
+        ConfigError::ParseError {
+            origin,
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+            path: (!path.is_empty()).then(|| path.join(".")),
+            message: error.into_inner().to_string(),
+        }
+    }
+
+    // Takes the variables to read, which are `env::vars()` outside of tests
+    fn environment_layers(
+        document: &Value,
+        variables: impl IntoIterator<Item = (String, String)>,
+    ) -> Vec<ConfigLayer> {
+        let mut variables: Vec<(String, String)> = variables
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+            .collect();
 
-        return config;
+        variables.sort();
+
+        variables
+            .into_iter()
+            .map(|(key, value)| {
+                let setting = &key[ENV_PREFIX.len()..];
+                let value = serde_json::from_str(&value).unwrap_or(Value::String(value));
+
+                ConfigLayer {
+                    kind: ConfigLayerKind::Environment,
+                    name: format!("environment {}", key),
+                    document: LauncherConfig::environment_override(
+                        Some(document),
+                        &setting.split("__").collect::<Vec<_>>(),
+                        value,
+                    ),
+                    text: None,
+                }
+            })
+            .collect()
     }
-}
 
-impl<'a> LauncherConfig<'a> {
-    fn create_config_file(&self) {
-        let config_data = serde_json::to_string_pretty(&self.config)
+    // Builds a partial document setting one value. Variable names are upper case, so each segment
+    // is matched to the existing settings case-insensitively
+    fn environment_override(document: Option<&Value>, setting: &[&str], value: Value) -> Value {
+        let Some((segment, rest)) = setting.split_first() else {
+            return value;
+        };
+
+        match document {
+            Some(Value::Array(list)) => {
+                let name = list
+                    .iter()
+                    .filter_map(|item| item.get("name").and_then(Value::as_str))
+                    .find(|name| name.eq_ignore_ascii_case(segment))
+                    .unwrap_or(segment);
+                let item = list
+                    .iter()
+                    .find(|item| item.get("name").and_then(Value::as_str) == Some(name));
+                let mut entry = LauncherConfig::environment_override(item, rest, value);
+
+                if let Value::Object(map) = &mut entry {
+                    map.insert("name".to_string(), Value::from(name));
+                }
+
+                Value::Array(vec![entry])
+            }
+            document => {
+                let map = document.and_then(Value::as_object);
+                let key = map
+                    .and_then(|map| map.keys().find(|key| key.eq_ignore_ascii_case(segment)))
+                    .cloned()
+                    .unwrap_or_else(|| segment.to_lowercase());
+                let child = map.and_then(|map| map.get(&key));
+                let mut overrides = Map::new();
+
+                overrides.insert(
+                    key,
+                    LauncherConfig::environment_override(child, rest, value),
+                );
+
+                Value::Object(overrides)
+            }
+        }
+    }
+
+    /// Writes the config's JSON Schema next to a config file, for the file's `$schema` to refer to.
+    pub fn write_schema_file(config_file_path: &str) -> Result<(), ConfigError> {
+        let schema_file_path = Path::new(config_file_path)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(SCHEMA_FILE_NAME);
+        let schema = serde_json::to_string_pretty(&config_schema())
+            .unwrap_or_else(|e| panic!("Error creating config schema: {}", e));
+
+        fs::write(&schema_file_path, schema).map_err(|error| ConfigError::WriteError {
+            file: schema_file_path.to_string_lossy().into_owned(),
+            error,
+        })
+    }
+
+    /// Changes settings through the typed config. Only the settings that changed are added to the
+    /// local config file's layer, so values from the other layers are not copied into it.
+    pub fn update(&mut self, change: impl FnOnce(&mut ConfigData)) -> Result<(), ConfigError> {
+        let mut config = self.config.clone();
+
+        change(&mut config);
+
+        let before = serde_json::to_value(&self.config)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+        let after = serde_json::to_value(&config)
             .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
 
-        let data: &str = &config_data;
-        let config_file_path = Path::new(self.file_path);
+        let changes: Vec<(String, Value)> = flatten(&after)
+            .into_iter()
+            .filter(|(path, value)| {
+                let segments: Vec<&str> = path.split('.').collect();
+
+                get_path(&before, &segments) != Some(value)
+            })
+            .map(|(path, value)| (path, value.clone()))
+            .collect();
+
+        for (path, value) in changes {
+            self.set(&path.split('.').collect::<Vec<_>>(), value)?;
+        }
 
-        fs::write(config_file_path, data)
-            .expect(format!("Error creating config file: {}", self.file_path).as_str());
+        Ok(())
     }
 
-    fn read_config_file(file_path: &'a str) -> Result<ConfigData, String> {
-        let config_data_string = fs::read_to_string(file_path).expect("Error reading config file");
-        let parsed_config: Result<ConfigData, serde_json::Error> =
-            serde_json::from_str(&config_data_string);
+    /// Looks up the effective value of a setting by its path segments.
+    pub fn get(&self, path: &[&str]) -> Option<Value> {
+        let document = serde_json::to_value(&self.config)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+        get_path(&document, path).cloned()
+    }
+
+    /// Sets one setting in the local config file's layer. The change is checked against the rest
+    /// of the config and undone if it makes the config invalid.
+    pub fn set(&mut self, path: &[&str], value: Value) -> Result<(), ConfigError> {
+        let previous_layers = self.layers.clone();
+
+        let local_layer = self.local_layer_mut();
+
+        set_path(&mut local_layer.document, path, value);
+
+        // The file's text no longer matches, so errors cannot point at a line in it
+        local_layer.text = None;
+
+        match self.merge_layers() {
+            Ok(config) => {
+                self.config = config;
+
+                Ok(())
+            }
+            Err(error) => {
+                self.layers = previous_layers;
+
+                Err(error)
+            }
+        }
+    }
+
+    /// Writes the local config file's layer back to `file_path`. The file is replaced atomically,
+    /// so it is never left half written, and the previous versions are kept as backups.
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let document = self
+            .layers
+            .iter()
+            .find(|layer| layer.kind == ConfigLayerKind::LocalFile)
+            .map_or(Value::Object(Map::new()), |layer| layer.document.clone());
+
+        let config_data = serde_json::to_string_pretty(&document)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+        let write_error = |error| ConfigError::WriteError {
+            file: self.file_path.clone(),
+            error,
+        };
+
+        if file_exists(&self.file_path) {
+            rotate_backups(&self.file_path, BACKUP_COUNT).map_err(write_error)?;
+        }
+
+        write_atomically(&self.file_path, &config_data).map_err(write_error)
+    }
+
+    fn local_layer_mut(&mut self) -> &mut ConfigLayer {
+        let position = self
+            .layers
+            .iter()
+            .position(|layer| layer.kind >= ConfigLayerKind::LocalFile)
+            .unwrap_or(self.layers.len());
+
+        if self.layers.get(position).map(|layer| layer.kind) != Some(ConfigLayerKind::LocalFile) {
+            let mut document = Map::new();
+
+            document.insert("version".to_string(), Value::from(CURRENT_VERSION));
+
+            self.layers.insert(
+                position,
+                ConfigLayer {
+                    kind: ConfigLayerKind::LocalFile,
+                    name: format!("local config {}", self.file_path),
+                    document: Value::Object(document),
+                    text: None,
+                },
+            );
+        }
+
+        &mut self.layers[position]
+    }
+
+    /// Writes the whole config to `file_path`, along with its schema. An existing file is kept
+    /// as a backup.
+    pub fn create_config_file(&self) -> Result<(), ConfigError> {
+        let config = ConfigData {
+            schema: Some(SCHEMA_FILE_NAME.to_string()),
+            ..self.config.clone()
+        };
 
-        match parsed_config {
-            Ok(data) => Ok(data),
-            Err(error) => Err(format!("Error parsing config file: {}", error)),
+        let config_data = serde_json::to_string_pretty(&config)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+        let write_error = |error| ConfigError::WriteError {
+            file: self.file_path.clone(),
+            error,
+        };
+
+        if file_exists(&self.file_path) {
+            rotate_backups(&self.file_path, BACKUP_COUNT).map_err(write_error)?;
         }
+
+        write_atomically(&self.file_path, &config_data).map_err(write_error)?;
+
+        LauncherConfig::write_schema_file(&self.file_path)
+    }
+
+    // Returns the file's document too when it had to be upgraded, as it was before the upgrade
+    fn read_config_file(
+        kind: ConfigLayerKind,
+        file_path: &str,
+    ) -> Result<(ConfigLayer, Option<Value>), ConfigError> {
+        let name = match kind {
+            ConfigLayerKind::UserFile => format!("user config {}", file_path),
+            _ => format!("local config {}", file_path),
+        };
+
+        let config_data_string =
+            fs::read_to_string(file_path).map_err(|error| ConfigError::ReadError {
+                file: file_path.to_string(),
+                error,
+            })?;
+
+        let original_document: Value = serde_json::from_str(&config_data_string)
+            .map_err(|error| LauncherConfig::parse_error(&name, error))?;
+
+        let mut document = original_document.clone();
+
+        migrate(&mut document).map_err(|message| ConfigError::MigrationError {
+            file: file_path.to_string(),
+            message,
+        })?;
+
+        let original_document = (document != original_document).then_some(original_document);
+
+        // Errors point at the file as it is on disk until the upgrade is written. Settings the
+        // file does not have are not written into it, they come from the defaults layer. Writing
+        // them would make the file override the user config file for every one
+        Ok((
+            ConfigLayer {
+                kind,
+                name,
+                document,
+                text: Some(config_data_string),
+            },
+            original_document,
+        ))
     }
 
-    fn config_file_exists(file_path: &'a str) -> bool {
+    // Keeps the original file next to the upgraded one in case the upgrade lost something
+    fn write_upgraded_config_file(
+        file_path: &str,
+        original_document: &Value,
+        document: &Value,
+    ) -> Result<String, ConfigError> {
+        let backup_path = format!("{}.v{}.bak", file_path, document_version(original_document));
+
+        fs::copy(file_path, &backup_path).map_err(|error| ConfigError::WriteError {
+            file: backup_path.clone(),
+            error,
+        })?;
+
+        let config_data = serde_json::to_string_pretty(document)
+            .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+        write_atomically(file_path, &config_data).map_err(|error| ConfigError::WriteError {
+            file: file_path.to_string(),
+            error,
+        })?;
+
+        // The schema of the new version describes settings the old schema file does not know
+        if document.get("$schema").is_some() {
+            LauncherConfig::write_schema_file(file_path)?;
+        }
+
+        println!(
+            "Upgraded config file {} to version {}, the original was saved to {}",
+            file_path,
+            document_version(document),
+            backup_path
+        );
+
+        Ok(config_data)
+    }
+
+    // For syntax errors, which serde_json reports with a location
+    fn parse_error(origin: &str, error: serde_json::Error) -> ConfigError {
+        let location_suffix = format!(" at line {} column {}", error.line(), error.column());
+        let message = error.to_string();
+
+        ConfigError::ParseError {
+            origin: origin.to_string(),
+            line: Some(error.line()),
+            column: Some(error.column()),
+            path: None,
+            message: message.trim_end_matches(&location_suffix).to_string(),
+        }
+    }
+
+    fn config_file_exists(file_path: &str) -> bool {
         file_exists(file_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn file_layer(kind: ConfigLayerKind, name: &str, document: Value) -> ConfigLayer {
+        ConfigLayer {
+            kind,
+            name: name.to_string(),
+            text: Some(serde_json::to_string_pretty(&document).unwrap()),
+            document,
+        }
+    }
+
+    // The defaults, a user file, a local file and the given environment variables
+    fn load(user: Value, local: Value, variables: &[(&str, &str)]) -> LauncherConfig {
+        let mut launcher_config = LauncherConfig::with_defaults("launcher_config.json");
+
+        launcher_config
+            .layers
+            .push(file_layer(ConfigLayerKind::UserFile, "user config", user));
+        launcher_config.layers.push(file_layer(
+            ConfigLayerKind::LocalFile,
+            "local config",
+            local,
+        ));
+
+        let variables = variables
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()));
+        let environment_layers =
+            LauncherConfig::environment_layers(&launcher_config.merged_document(), variables);
+
+        launcher_config.layers.extend(environment_layers);
+
+        launcher_config
+    }
+
+    fn source(launcher_config: &LauncherConfig, path: &str) -> Option<String> {
+        launcher_config
+            .settings()
+            .into_iter()
+            .find(|(setting, _, _)| setting == path)
+            .map(|(_, _, source)| source)
+    }
+
+    #[test]
+    fn higher_layers_win() {
+        let mut launcher_config = load(
+            json!({"danser": {"enabled": true, "settings_name": "user"}, "osu_trainer": {"enabled": true}}),
+            json!({"danser": {"settings_name": "local"}, "osu_trainer": {"enabled": false}}),
+            &[("OSU_LAUNCHER__DANSER__SETTINGS_NAME", "environment")],
+        );
+
+        launcher_config.config = launcher_config.merge_layers().unwrap();
+
+        assert!(launcher_config.config.danser.enabled);
+        assert!(!launcher_config.config.osu_trainer.enabled);
+        assert_eq!(
+            launcher_config.config.danser.settings_name.as_deref(),
+            Some("environment")
+        );
+        assert_eq!(
+            source(&launcher_config, "danser.enabled").as_deref(),
+            Some("user config")
+        );
+        assert_eq!(
+            source(&launcher_config, "osu_trainer.enabled").as_deref(),
+            Some("local config")
+        );
+        assert_eq!(
+            source(&launcher_config, "danser.settings_name").as_deref(),
+            Some("environment OSU_LAUNCHER__DANSER__SETTINGS_NAME")
+        );
+        assert_eq!(
+            source(&launcher_config, "danser.render.max_parallel").as_deref(),
+            Some("defaults")
+        );
+    }
+
+    #[test]
+    fn settings_set_in_the_local_file_stay_overridden_by_the_environment() {
+        let mut launcher_config = load(
+            json!({}),
+            json!({}),
+            &[("OSU_LAUNCHER__DANSER__ENABLED", "false")],
+        );
+
+        launcher_config
+            .set(&["danser", "enabled"], json!(true))
+            .unwrap();
+
+        let source = launcher_config
+            .setting_source(&["danser", "enabled"])
+            .unwrap();
+
+        assert!(!launcher_config.config.danser.enabled);
+        assert!(source.kind == ConfigLayerKind::Environment);
+        assert!(launcher_config
+            .setting_source(&["danser", "settings_name"])
+            .is_some_and(|layer| layer.kind == ConfigLayerKind::Defaults));
+    }
+
+    #[test]
+    fn environment_variables_pick_custom_apps_by_name() {
+        let tosu = json!({"name": "tosu", "path": "/opt/tosu", "executable_name": "tosu", "enabled": false});
+        let mut launcher_config = load(
+            json!({}),
+            json!({"custom_apps": [tosu]}),
+            &[
+                ("OSU_LAUNCHER__CUSTOM_APPS__TOSU__ENABLED", "true"),
+                (
+                    "OSU_LAUNCHER__CUSTOM_APPS__TOSU__ARGS",
+                    "[\"--port\", \"24050\"]",
+                ),
+            ],
+        );
+
+        launcher_config.config = launcher_config.merge_layers().unwrap();
+
+        let tosu = &launcher_config.config.custom_apps[0];
+
+        assert_eq!(launcher_config.config.custom_apps.len(), 1);
+        assert_eq!(tosu.name, "tosu");
+        assert!(tosu.enabled);
+        assert_eq!(tosu.launch.args, ["--port", "24050"]);
+        assert_eq!(
+            source(&launcher_config, "custom_apps.tosu.enabled").as_deref(),
+            Some("environment OSU_LAUNCHER__CUSTOM_APPS__TOSU__ENABLED")
+        );
+    }
+
+    #[test]
+    fn environment_overrides_match_settings_ignoring_case() {
+        let document =
+            json!({"danser": {"settings_name": "default"}, "custom_apps": [{"name": "tosu"}]});
+
+        assert_eq!(
+            LauncherConfig::environment_override(
+                Some(&document),
+                &["DANSER", "SETTINGS_NAME"],
+                json!("streaming")
+            ),
+            json!({"danser": {"settings_name": "streaming"}})
+        );
+        assert_eq!(
+            LauncherConfig::environment_override(
+                Some(&document),
+                &["CUSTOM_APPS", "TOSU", "ENABLED"],
+                json!(true)
+            ),
+            json!({"custom_apps": [{"name": "tosu", "enabled": true}]})
+        );
+        // Settings the document does not have yet are lower case
+        assert_eq!(
+            LauncherConfig::environment_override(
+                Some(&document),
+                &["DANSER", "OUTPUT_TEMPLATE"],
+                json!("{player}")
+            ),
+            json!({"danser": {"output_template": "{player}"}})
+        );
+    }
+
+    #[test]
+    fn environment_values_are_read_as_json_when_they_can_be() {
+        let launcher_config = load(
+            json!({}),
+            json!({}),
+            &[
+                ("OSU_LAUNCHER__DANSER__ENABLED", "true"),
+                ("OSU_LAUNCHER__DANSER__RENDER__MAX_PARALLEL", "2"),
+                ("OSU_LAUNCHER__DANSER__SETTINGS_NAME", "streaming"),
+                (
+                    "OSU_LAUNCHER__OSU__ARGS",
+                    "[\"-devserver\", \"example.com\"]",
+                ),
+                ("OSU_LAUNCHER__OSU__CWD", "null"),
+                ("OTHER__DANSER__ENABLED", "false"),
+            ],
+        );
+
+        let values: Vec<Value> = launcher_config
+            .layers
+            .iter()
+            .filter(|layer| layer.kind == ConfigLayerKind::Environment)
+            .map(|layer| layer.document.clone())
+            .collect();
+
+        assert_eq!(
+            values,
+            [
+                json!({"danser": {"enabled": true}}),
+                json!({"danser": {"render": {"max_parallel": 2}}}),
+                json!({"danser": {"settings_name": "streaming"}}),
+                json!({"osu": {"args": ["-devserver", "example.com"]}}),
+                json!({"osu": {"cwd": null}}),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_values_blame_the_layer_that_sets_them() {
+        let launcher_config = load(
+            json!({}),
+            json!({}),
+            &[("OSU_LAUNCHER__DANSER__ENABLED", "yes")],
+        );
+
+        assert!(matches!(
+            launcher_config.merge_layers(),
+            Err(ConfigError::ParseError { origin, path, .. })
+                if origin == "environment OSU_LAUNCHER__DANSER__ENABLED"
+                    && path.as_deref() == Some("danser.enabled")
+        ));
+
+        let launcher_config = load(
+            json!({}),
+            json!({"danser": {"render": {"max_parallel": "two"}}}),
+            &[],
+        );
+
+        assert!(matches!(
+            launcher_config.merge_layers(),
+            Err(ConfigError::ParseError { origin, line: Some(4), column: Some(23), .. })
+                if origin == "local config"
+        ));
+    }
+
+    #[test]
+    fn unknown_settings_are_reported_where_they_are() {
+        let launcher_config = load(
+            json!({}),
+            json!({"danser": {"trigger": {"type": "filter", "player": ["peppy"]}}}),
+            &[],
+        );
+
+        assert!(matches!(
+            LauncherConfig::check_unknown_settings(&launcher_config.layers),
+            Err(ConfigError::ParseError { origin, line: Some(5), column: Some(17), path, .. })
+                if origin == "local config"
+                    && path.as_deref() == Some("danser.trigger.player")
+        ));
+
+        let launcher_config = load(
+            json!({}),
+            json!({}),
+            &[("OSU_LAUNCHER__CUSTOM_APPS__TOSU__READY__PROT", "24050")],
+        );
+
+        assert!(matches!(
+            LauncherConfig::check_unknown_settings(&launcher_config.layers),
+            Err(ConfigError::ParseError { origin, line: None, path, .. })
+                if origin == "environment OSU_LAUNCHER__CUSTOM_APPS__TOSU__READY__PROT"
+                    && path.as_deref() == Some("custom_apps.TOSU.ready.prot")
+        ));
+    }
+
+    #[test]
+    fn unknown_settings_in_profiles_are_reported_when_they_are_applied() {
+        let profiles = json!({"profiles": {"streaming": {"danser": {"enabeld": true}}}});
+        let mut launcher_config = load(json!({}), profiles, &[]);
+
+        // Profiles can hold anything until they are applied
+        launcher_config.config = launcher_config.merge_layers().unwrap();
+
+        assert!(matches!(
+            launcher_config.apply_profile(Some("streaming")),
+            Err(ConfigError::ParseError { origin, path, .. })
+                if origin == "profile streaming" && path.as_deref() == Some("danser.enabeld")
+        ));
+    }
+
+    #[test]
+    fn known_settings_pass() {
+        let launcher_config = load(
+            json!({"runner": {"type": "wine", "prefix": "~/.wine-osu"}}),
+            json!({
+                "danser": {"trigger": {"type": "filter", "modes": ["osu"], "players": ["peppy"]}},
+                "custom_apps": [{"name": "tosu", "enabled": true, "ready": {"port": 24050}, "env": {"PORT": "24050"}}],
+                "profiles": {"streaming": {"danser": {"enabled": true}}},
+            }),
+            &[("OSU_LAUNCHER__OPEN_TABLET_DRIVER__RESTART__BACKOFF", "2")],
+        );
+
+        assert!(LauncherConfig::check_unknown_settings(&launcher_config.layers).is_ok());
+    }
+
+    #[test]
+    fn custom_app_names_must_differ_ignoring_case() {
+        let launcher_config = load(
+            json!({}),
+            json!({"custom_apps": [
+                {"name": "tosu", "enabled": true},
+                {"name": "overlay", "enabled": true},
+                {"name": "Tosu", "enabled": false},
+            ]}),
+            &[],
+        );
+
+        assert!(matches!(
+            launcher_config.merge_layers(),
+            Err(ConfigError::ParseError { origin, line: Some(12), path, message, .. })
+                if origin == "local config"
+                    && path.as_deref() == Some("custom_apps.2.name")
+                    && message == "another custom app is already named \"Tosu\""
+        ));
+    }
+
+    #[test]
+    fn custom_apps_cannot_use_built_in_names() {
+        let launcher_config = load(
+            json!({"custom_apps": [{"name": "Danser", "enabled": true}]}),
+            json!({}),
+            &[],
+        );
+
+        assert!(matches!(
+            launcher_config.merge_layers(),
+            Err(ConfigError::ParseError { origin, path, message, .. })
+                if origin == "user config"
+                    && path.as_deref() == Some("custom_apps.0.name")
+                    && message.starts_with("\"Danser\" is the name of a built-in application")
+        ));
+    }
+
+    #[test]
+    fn custom_apps_of_different_layers_with_one_name_are_merged() {
+        let tosu = json!({"name": "tosu", "path": "/opt/tosu", "executable_name": "tosu", "enabled": false});
+        let mut launcher_config = load(
+            json!({"custom_apps": [tosu]}),
+            json!({"custom_apps": [{"name": "tosu", "enabled": true}]}),
+            &[],
+        );
+
+        launcher_config.config = launcher_config.merge_layers().unwrap();
+
+        assert_eq!(launcher_config.config.custom_apps.len(), 1);
+        assert!(launcher_config.config.custom_apps[0].enabled);
+
+        // `config set` adds an entry for a name it does not find, which must not be a duplicate
+        let error = launcher_config.set(&["custom_apps", "TOSU", "enabled"], json!(false));
+
+        assert!(matches!(
+            error,
+            Err(ConfigError::ParseError { message, .. })
+                if message == "another custom app is already named \"TOSU\""
+        ));
+        assert!(launcher_config.config.custom_apps[0].enabled);
+        assert_eq!(
+            launcher_config.get(&["custom_apps", "tosu", "enabled"]),
+            Some(json!(true))
+        );
+    }
+}
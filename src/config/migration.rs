@@ -0,0 +1,96 @@
+use serde_json::{Map, Value};
+
+/// The version written to new config files. Bump this and append a step to `MIGRATIONS` whenever
+/// the shape of the config changes in a way that default values alone cannot fix.
+pub const CURRENT_VERSION: u64 = 1;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Returns the version of a config document. Documents from before versioning was introduced have
+/// no `version` field and are treated as version 0.
+pub fn document_version(document: &Value) -> u64 {
+    document.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades a config document to `CURRENT_VERSION` one step at a time.
+pub fn migrate(document: &mut Value) -> Result<(), String> {
+    let version = document_version(document);
+
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "Config file version {} is newer than the latest supported version {}",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    let map = document
+        .as_object_mut()
+        .ok_or("Config file must contain a JSON object")?;
+
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(map);
+
+        map.insert("version".to_string(), Value::from(from_version as u64 + 1));
+    }
+
+    Ok(())
+}
+
+// Version 0 configs only lack the version field itself, which `migrate` adds.
+fn migrate_v0_to_v1(_: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn upgrades_unversioned_documents() {
+        let mut document = json!({"danser": {"enabled": true}});
+
+        assert_eq!(document_version(&document), 0);
+        assert_eq!(migrate(&mut document), Ok(()));
+        assert_eq!(
+            document,
+            json!({"danser": {"enabled": true}, "version": CURRENT_VERSION})
+        );
+    }
+
+    #[test]
+    fn leaves_current_documents_alone() {
+        let mut document = json!({"version": CURRENT_VERSION, "danser": {"enabled": true}});
+        let original = document.clone();
+
+        assert_eq!(migrate(&mut document), Ok(()));
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn rejects_newer_documents() {
+        let mut document = json!({"version": CURRENT_VERSION + 1});
+
+        assert_eq!(
+            migrate(&mut document),
+            Err(format!(
+                "Config file version {} is newer than the latest supported version {}",
+                CURRENT_VERSION + 1,
+                CURRENT_VERSION
+            ))
+        );
+        assert_eq!(document, json!({"version": CURRENT_VERSION + 1}));
+    }
+
+    #[test]
+    fn rejects_documents_that_are_not_objects() {
+        for mut document in [json!([]), json!("osu!"), Value::Null] {
+            assert_eq!(
+                migrate(&mut document),
+                Err("Config file must contain a JSON object".to_string())
+            );
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod data;
 pub mod error;
 pub mod manager;
+pub mod migration;
+pub mod schema;
 pub mod traits;
@@ -0,0 +1,230 @@
+use schemars::schema_for;
+use serde_json::{Map, Value};
+
+use super::data::ConfigData;
+
+/// Written next to the config file, which refers to it with `$schema`.
+pub const SCHEMA_FILE_NAME: &str = "launcher_config.schema.json";
+
+/// The JSON Schema of the config file, with the defaults of every setting filled in from
+/// `ConfigData::new`.
+pub fn config_schema() -> Value {
+    let mut schema = serde_json::to_value(schema_for!(ConfigData))
+        .unwrap_or_else(|e| panic!("Error creating config schema: {}", e));
+    let defaults = serde_json::to_value(ConfigData::new())
+        .unwrap_or_else(|e| panic!("Error creating config data: {}", e));
+
+    let mut definitions = schema
+        .get_mut("definitions")
+        .map(Value::take)
+        .and_then(|definitions| match definitions {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    add_defaults(&mut schema, &mut definitions, &defaults);
+
+    // Config files only need the settings they change, the rest comes from the other layers. Only
+    // custom apps need their name, to be told apart
+    for object_schema in std::iter::once(&mut schema).chain(definitions.values_mut()) {
+        let Some(object_schema) = object_schema.as_object_mut() else {
+            continue;
+        };
+
+        if let Some(Value::Array(required)) = object_schema.get_mut("required") {
+            required.retain(|property| property == "name");
+
+            if required.is_empty() {
+                object_schema.remove("required");
+            }
+        }
+    }
+
+    schema["definitions"] = Value::Object(definitions);
+
+    schema
+}
+
+// Settings of the app structs are described in `definitions`, so follow references into them
+fn add_defaults(schema: &mut Value, definitions: &mut Map<String, Value>, defaults: &Value) {
+    let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) else {
+        return;
+    };
+
+    for (name, property) in properties.iter_mut() {
+        let Some(default) = defaults.get(name) else {
+            continue;
+        };
+
+        property["default"] = default.clone();
+
+        let definition_name = definition_name(property);
+
+        if let Some(mut definition) = definition_name
+            .as_ref()
+            .and_then(|definition_name| definitions.remove(definition_name))
+        {
+            add_defaults(&mut definition, definitions, default);
+
+            definitions.insert(definition_name.unwrap(), definition);
+        }
+    }
+}
+
+// schemars wraps references in `allOf` when the property has a description
+fn definition_name(property: &Value) -> Option<String> {
+    let reference = property
+        .get("$ref")
+        .or_else(|| property.get("allOf")?.get(0)?.get("$ref"))?
+        .as_str()?;
+
+    reference.strip_prefix("#/definitions/").map(str::to_string)
+}
+
+/// Whether a dotted setting path, split into segments, names a setting the config has. Keys of
+/// free-form settings like `env` and `profiles` are always allowed.
+pub fn is_known_setting(path: &[&str]) -> bool {
+    is_known_setting_in(&config_schema(), path)
+}
+
+/// Like `is_known_setting`, with the schema from `config_schema`, to check many settings without
+/// creating it each time.
+pub fn is_known_setting_in(schema: &Value, path: &[&str]) -> bool {
+    is_known_in(schema, schema, path)
+}
+
+fn is_known_in(schema: &Value, current: &Value, path: &[&str]) -> bool {
+    let current = resolve_reference(schema, current);
+
+    // Enums and optional settings list their shapes, the setting is known if any of them has it
+    let alternatives = current.get("oneOf").or_else(|| current.get("anyOf"));
+
+    if let Some(Value::Array(alternatives)) = alternatives {
+        return alternatives
+            .iter()
+            .any(|alternative| is_known_in(schema, alternative, path));
+    }
+
+    let Some((segment, rest)) = path.split_first() else {
+        return true;
+    };
+
+    let next = if let Some(property) = current
+        .get("properties")
+        .and_then(|properties| properties.get(*segment))
+    {
+        property
+    } else if current.get("properties").is_some() {
+        return false;
+    } else if let Some(items) = current.get("items") {
+        items
+    } else {
+        match current.get("additionalProperties") {
+            Some(Value::Bool(allowed)) => return *allowed,
+            Some(additional_properties) => additional_properties,
+            // An empty schema, like the one of a serde_json::Value, allows anything
+            None if current.get("type").is_none() => return true,
+            None => return false,
+        }
+    };
+
+    is_known_in(schema, next, rest)
+}
+
+fn resolve_reference<'s>(schema: &'s Value, mut current: &'s Value) -> &'s Value {
+    while let Some(definition) = definition_name(current) {
+        match schema["definitions"].get(&definition) {
+            Some(resolved) => current = resolved,
+            None => break,
+        }
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(schema: &Value, path: &str) -> bool {
+        is_known_setting_in(schema, &path.split('.').collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn knows_nested_and_flattened_settings() {
+        let schema = config_schema();
+
+        for path in [
+            "$schema",
+            "version",
+            "danser.enabled",
+            "danser.render.max_parallel",
+            "danser.settle.interval",
+            // Launch options are flattened into every application
+            "osu.args",
+            "open_tablet_driver.restart.backoff",
+            "osu_trainer.log.max_size_mb",
+            "custom_apps.tosu.name",
+            "custom_apps.tosu.depends_on",
+            "shutdown.timeout",
+        ] {
+            assert!(known(&schema, path), "{} should be known", path);
+        }
+
+        for path in [
+            "danser.enabeld",
+            "danser.render.max_paralel",
+            "open_tablet_driver.restart.bakoff",
+            "custom_apps.tosu.depend_on",
+            "osu.enabled.value",
+            "launcher",
+        ] {
+            assert!(!known(&schema, path), "{} should be unknown", path);
+        }
+    }
+
+    #[test]
+    fn knows_the_settings_of_every_variant() {
+        let schema = config_schema();
+
+        for path in [
+            "runner.type",
+            "runner.prefix",
+            "runner.binary",
+            "runner.command",
+            "custom_apps.tosu.runner.env.STEAM_COMPAT_DATA_PATH",
+            "danser.trigger.type",
+            "danser.trigger.key",
+            "danser.trigger.path",
+            "danser.trigger.modes",
+            "danser.trigger.players",
+            "custom_apps.tosu.ready.port",
+            "open_tablet_driver.ready.delay",
+        ] {
+            assert!(known(&schema, path), "{} should be known", path);
+        }
+
+        for path in [
+            "runner.prefx",
+            "danser.trigger.player",
+            "custom_apps.tosu.ready.prot",
+        ] {
+            assert!(!known(&schema, path), "{} should be unknown", path);
+        }
+    }
+
+    #[test]
+    fn allows_any_key_in_free_form_settings() {
+        let schema = config_schema();
+
+        for path in [
+            "osu.env.WINEDEBUG",
+            "custom_apps.tosu.env.PORT",
+            "profiles.streaming.danser.enabled",
+            "profiles.streaming.anything",
+        ] {
+            assert!(known(&schema, path), "{} should be known", path);
+        }
+    }
+}
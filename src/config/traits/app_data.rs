@@ -1,34 +1,57 @@
 use std::{
     path::{Path, PathBuf},
     process::Command,
+    sync::{atomic::Ordering, Arc},
 };
 
 use super::super::error::app_data_error::AppDataError;
 use crate::{
-    config::error::app_process_error::AppProcessError,
-    util::file::{download_file_to, path_exists},
+    config::{
+        data::{
+            AppProcess, LaunchContext, LaunchOptions, ProcessState, ResolvedLaunchOptions, Runner,
+        },
+        error::app_process_error::AppProcessError,
+    },
+    launch::{
+        output::{spawn_logged, AppLog},
+        restart::supervise,
+        runner::runner_command,
+    },
+    util::{
+        file::{download_file_to, path_exists},
+        path::{expand_path, replace_placeholders},
+    },
 };
 use async_trait::async_trait;
-use tokio::task::JoinHandle;
 
 #[async_trait]
-pub trait Application {
+pub trait Application: Sync {
+    fn get_name(&self) -> String;
+
+    fn get_launch_options(&self) -> &LaunchOptions;
+
     fn get_enabled(&self) -> bool {
         false
     }
 
+    fn set_enabled(&mut self, enabled: bool);
+
     fn get_path(&self) -> Option<String> {
         None
     }
 
-    fn path_exists(&self) -> bool {
-        if self.get_path().is_none() {
-            return false;
-        }
+    /// The configured path with `~` and environment variables expanded.
+    fn get_expanded_path(&self) -> Result<String, AppDataError> {
+        let path = self.get_path().ok_or(AppDataError::PathNotFound)?;
 
-        let path = self.get_path().unwrap();
+        expand_path(&path).map_err(AppDataError::PathExpansionFailed)
+    }
 
-        path_exists(&path)
+    fn path_exists(&self) -> bool {
+        match self.get_expanded_path() {
+            Ok(path) => path_exists(&path),
+            Err(_) => false,
+        }
     }
 
     fn executable_exists(&self) -> bool {
@@ -53,27 +76,32 @@ pub trait Application {
     }
 
     fn get_executable_path(&self) -> Result<PathBuf, AppDataError> {
-        let path = self.get_path();
-        let executable_name = self.get_executable_name();
-
-        if path.is_none() {
-            return Err(AppDataError::PathNotFound);
-        }
-
-        if executable_name.is_none() {
-            return Err(AppDataError::ExecutableNameNotFound);
-        }
+        let path = self.get_expanded_path()?;
+        let executable_name = self
+            .get_executable_name()
+            .ok_or(AppDataError::ExecutableNameNotFound)?;
+        let executable_name =
+            expand_path(&executable_name).map_err(AppDataError::PathExpansionFailed)?;
 
-        let executable_path = Path::new(&path.unwrap())
-            .join(executable_name.unwrap())
+        let executable_path = Path::new(&path)
+            .join(executable_name)
             .to_string_lossy()
             .into_owned();
 
         Ok(PathBuf::from(executable_path))
     }
 
+    /// Whether the user allowed the application to be downloaded. Applications without a
+    /// `download` setting are downloaded whenever they have a download URL.
+    fn get_download(&self) -> bool {
+        true
+    }
+
     fn can_download(&self) -> bool {
-        self.get_enabled() && self.get_public_download_url().is_some() && !self.path_exists()
+        self.get_enabled()
+            && self.get_download()
+            && self.get_public_download_url().is_some()
+            && !self.path_exists()
     }
 
     async fn download(&self) -> Result<PathBuf, AppDataError> {
@@ -83,7 +111,7 @@ pub trait Application {
 
         let file_name = format!("{}.zip", uuid::Uuid::new_v4());
 
-        let path_str = self.get_path().ok_or(AppDataError::PathNotFound)?;
+        let path_str = self.get_expanded_path()?;
         let download_location_path = Path::new(&path_str);
 
         let download_location = download_location_path
@@ -102,29 +130,192 @@ pub trait Application {
         Ok(PathBuf::from(download_location))
     }
 
-    fn try_spawn_process(
+    /// The executable that already running copies of the application are found by, or `None` when
+    /// they are not looked for.
+    fn get_running_executable(&self) -> Option<PathBuf> {
+        self.get_executable_path().ok()
+    }
+
+    /// How the application's executable is run, its own `runner` or the global one.
+    fn get_runner<'a>(&'a self, context: &'a LaunchContext) -> &'a Runner {
+        self.get_launch_options()
+            .runner
+            .as_ref()
+            .unwrap_or(&context.runner)
+    }
+
+    /// The application's `args`, `env` and `cwd` with the placeholders in them replaced.
+    fn resolve_launch_options(
         &self,
-    ) -> Result<JoinHandle<Result<std::process::ExitStatus, AppProcessError>>, AppProcessError>
-    {
-        if !self.get_enabled() || !self.executable_exists() {
+        context: &LaunchContext,
+    ) -> Result<ResolvedLaunchOptions, AppProcessError> {
+        let app_path = self.get_expanded_path().ok();
+        let placeholders = [
+            ("app_path", app_path.as_deref()),
+            ("osu_path", context.osu_path.as_deref()),
+            ("launcher_dir", Some(context.launcher_dir.as_str())),
+        ];
+        let replace = |value: &str| {
+            replace_placeholders(value, &placeholders).map_err(AppProcessError::AppLaunchError)
+        };
+
+        let launch_options = self.get_launch_options();
+        let args = launch_options
+            .args
+            .iter()
+            .map(|arg| replace(arg))
+            .collect::<Result<_, _>>()?;
+        let env = launch_options
+            .env
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), replace(value)?)))
+            .collect::<Result<_, AppProcessError>>()?;
+        let cwd = match &launch_options.cwd {
+            Some(cwd) => {
+                let cwd = expand_path(cwd).map_err(AppProcessError::AppLaunchError)?;
+
+                Some(replace(&cwd)?)
+            }
+            None => None,
+        };
+
+        Ok(ResolvedLaunchOptions { args, env, cwd })
+    }
+
+    /// The command that launches the application through its runner, with its `args`, `env` and
+    /// `cwd` and the placeholders in them replaced.
+    fn command(&self, context: &LaunchContext) -> Result<Command, AppProcessError> {
+        let executable_path = self
+            .get_executable_path()
+            .map_err(|error| AppProcessError::AppLaunchError(error.to_string()))?;
+
+        if !executable_path.exists() {
             return Err(AppProcessError::AppNotFound);
         }
 
-        if let Ok(executable_path) = self.get_executable_path() {
-            let child_future = tokio::spawn(async move {
-                let mut process = Command::new(executable_path).spawn().map_err(|err| {
-                    AppProcessError::AppLaunchError(format!(
-                        "Failed to launch process: {}",
-                        err.to_string()
-                    ))
-                })?;
+        let launch_options = self.resolve_launch_options(context)?;
+        let mut command = runner_command(self.get_runner(context), &executable_path)
+            .map_err(AppProcessError::AppLaunchError)?;
 
-                process.wait().map_err(|_| AppProcessError::AppWaitError)
-            });
+        command.args(&launch_options.args);
+        command.envs(&launch_options.env);
 
-            return Ok(child_future);
+        if let Some(cwd) = &launch_options.cwd {
+            command.current_dir(cwd);
         }
 
-        Err(AppProcessError::AppNotFound)
+        Ok(command)
+    }
+
+    fn try_spawn_process(&self, context: &LaunchContext) -> Result<AppProcess, AppProcessError> {
+        if !self.get_enabled() {
+            return Err(AppProcessError::AppNotFound);
+        }
+
+        let name = self.get_name();
+        let launch_options = self.get_launch_options();
+        let log = AppLog::open(&name, &launch_options.log, &context.launcher_dir);
+
+        let command = self.command(context)?;
+        let process = spawn_logged(&command, &log).map_err(|err| {
+            AppProcessError::AppLaunchError(format!("Failed to launch process: {}", err))
+        })?;
+        let restart_options = launch_options.restart.clone();
+        let state = Arc::new(ProcessState::default());
+
+        state
+            .pid
+            .store(process.id().unwrap_or(0), Ordering::Relaxed);
+
+        let supervisor_state = state.clone();
+        let child_future = tokio::spawn(async move {
+            supervise(
+                &name,
+                &command,
+                process,
+                &log,
+                &restart_options,
+                &supervisor_state,
+            )
+            .await
+        });
+
+        Ok(AppProcess {
+            name: self.get_name(),
+            state,
+            handle: child_future,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::data::CustomAppData;
+
+    fn app(path: Option<&str>, launch: LaunchOptions) -> CustomAppData {
+        CustomAppData {
+            name: "tosu".to_string(),
+            path: path.map(String::from),
+            executable_name: Some("tosu".to_string()),
+            source: None,
+            enabled: true,
+            launch,
+        }
+    }
+
+    fn context(osu_path: Option<&str>) -> LaunchContext {
+        LaunchContext {
+            osu_path: osu_path.map(String::from),
+            launcher_dir: "/opt/launcher".to_string(),
+            runner: Runner::Native,
+        }
+    }
+
+    #[test]
+    fn replaces_placeholders_in_args_env_and_cwd() {
+        let launch = LaunchOptions {
+            args: vec![
+                "--songs={osu_path}/Songs".to_string(),
+                "--port=24050".to_string(),
+            ],
+            env: [("TOSU_CONFIG".to_string(), "{app_path}/tosu.env".to_string())].into(),
+            cwd: Some("{launcher_dir}/tosu".to_string()),
+            ..Default::default()
+        };
+
+        let resolved = app(Some("/opt/tosu"), launch)
+            .resolve_launch_options(&context(Some("/games/osu!")))
+            .unwrap();
+
+        assert_eq!(resolved.args, ["--songs=/games/osu!/Songs", "--port=24050"]);
+        assert_eq!(
+            resolved.env.get("TOSU_CONFIG").map(String::as_str),
+            Some("/opt/tosu/tosu.env")
+        );
+        assert_eq!(resolved.cwd.as_deref(), Some("/opt/launcher/tosu"));
+    }
+
+    #[test]
+    fn fails_when_a_placeholder_has_no_value() {
+        let launch = LaunchOptions {
+            args: vec!["--songs={osu_path}/Songs".to_string()],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            app(Some("/opt/tosu"), launch).resolve_launch_options(&context(None)),
+            Err(AppProcessError::AppLaunchError(message))
+                if message == "{osu_path} in \"--songs={osu_path}/Songs\" has no value"
+        ));
+
+        let launch = LaunchOptions {
+            cwd: Some("{app_path}".to_string()),
+            ..Default::default()
+        };
+
+        assert!(app(None, launch)
+            .resolve_launch_options(&context(Some("/games/osu!")))
+            .is_err());
     }
 }
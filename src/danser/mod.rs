@@ -0,0 +1,4 @@
+pub mod naming;
+pub mod queue;
+pub mod settle;
+pub mod trigger;
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+use serde_json::Value;
+
+use crate::{
+    config::{data::DanserData, traits::app_data::Application},
+    replay::{mods::acronyms, Replay},
+    util::{file::sanitize_file_name, path::replace_placeholders},
+};
+
+/// The name of the video for a replay, from Danser's `output_template`, without the extension.
+pub fn video_name(template: &str, replay_path: &Path) -> String {
+    let replay_stem = replay_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let replay = Replay::read(replay_path).ok();
+
+    let mods = replay.as_ref().map(|replay| match acronyms(replay.mods) {
+        mods if mods.is_empty() => "NM".to_string(),
+        mods => mods.join(""),
+    });
+    let accuracy = replay
+        .as_ref()
+        .map(|replay| format!("{:.2}", replay.accuracy() * 100.0));
+    // The day the player saw, not the UTC one, which differs around midnight
+    let date = replay
+        .as_ref()
+        .and_then(|replay| replay.played_at())
+        .map(|played_at| {
+            played_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        });
+
+    let placeholders = [
+        (
+            "player",
+            replay.as_ref().map(|replay| replay.player.as_str()),
+        ),
+        ("mods", mods.as_deref()),
+        ("accuracy", accuracy.as_deref()),
+        ("date", date.as_deref()),
+        (
+            "beatmap_md5",
+            replay.as_ref().map(|replay| replay.beatmap_md5.as_str()),
+        ),
+        ("replay_stem", Some(replay_stem.as_str())),
+    ];
+
+    match replace_placeholders(template, &placeholders) {
+        Ok(name) => sanitize_file_name(&name),
+        Err(error) => {
+            println!(
+                "Could not name the video of {} from danser.output_template, {}, naming it after the replay",
+                replay_stem, error
+            );
+
+            sanitize_file_name(&replay_stem)
+        }
+    }
+}
+
+/// Numbers the name, like `name (2)`, when a video already has it or another render is using it.
+pub fn unique_video_name(name: String, used_names: &HashSet<String>, videos_dir: &Path) -> String {
+    // Windows file names are not case sensitive
+    let is_taken =
+        |name: &str| used_names.contains(&name.to_lowercase()) || video_exists(videos_dir, name);
+
+    if !is_taken(&name) {
+        return name;
+    }
+
+    (2..)
+        .map(|number| format!("{} ({})", name, number))
+        .find(|numbered| !is_taken(numbered))
+        .unwrap()
+}
+
+/// The directory Danser saves videos to, from the Recording.OutputDir of the settings it renders
+/// with. Relative directories are in Danser's directory.
+pub fn videos_dir(danser: &DanserData, settings_name: &str) -> PathBuf {
+    let danser_path = PathBuf::from(danser.get_expanded_path().unwrap_or_default());
+    let settings_path = danser_path
+        .join("settings")
+        .join(format!("{}.json", settings_name));
+
+    let output_dir = fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|settings| serde_json::from_str::<Value>(&settings).ok())
+        .and_then(|settings| {
+            settings["Recording"]["OutputDir"]
+                .as_str()
+                .map(String::from)
+        })
+        .unwrap_or_else(|| "videos".to_string());
+
+    danser_path.join(output_dir)
+}
+
+// Danser adds the extension of the format it records to, so any extension counts
+fn video_exists(videos_dir: &Path, name: &str) -> bool {
+    let Ok(entries) = fs::read_dir(videos_dir) else {
+        return false;
+    };
+
+    entries.flatten().any(|entry| {
+        Path::new(&entry.file_name())
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().to_lowercase() == name.to_lowercase())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{config::data::ConfigData, replay::tests::fixture};
+
+    #[test]
+    fn names_videos_from_the_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay_path = dir.path().join("peppy - song [hard] (2024-01-04) Osu.osr");
+
+        fs::write(&replay_path, fixture(72)).unwrap();
+
+        // The fixture was played at 2024-01-04 21:20 UTC, which is another day in some time zones
+        let date = Utc
+            .with_ymd_and_hms(2024, 1, 4, 21, 20, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%Y-%m-%d")
+            .to_string();
+
+        assert_eq!(
+            video_name("{player} {mods} {accuracy}% {date}", &replay_path),
+            format!("peppy HDDT 91.74% {}", date)
+        );
+        assert_eq!(
+            video_name("{beatmap_md5}", &replay_path),
+            "0123456789abcdef0123456789abcdef"
+        );
+        assert_eq!(
+            video_name("{replay_stem}", &replay_path),
+            "peppy - song [hard] (2024-01-04) Osu"
+        );
+        // Text in braces that is not a placeholder is kept
+        assert_eq!(video_name("{player} {rank}", &replay_path), "peppy {rank}");
+    }
+
+    #[test]
+    fn names_videos_with_no_mods_as_nm() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay_path = dir.path().join("nomod.osr");
+
+        fs::write(&replay_path, fixture(0)).unwrap();
+
+        assert_eq!(video_name("{player} {mods}", &replay_path), "peppy NM");
+    }
+
+    #[test]
+    fn names_videos_of_unreadable_replays_after_the_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay_path = dir.path().join("cut off.osr");
+
+        fs::write(&replay_path, &fixture(0)[..30]).unwrap();
+
+        assert_eq!(video_name("{player} {mods}", &replay_path), "cut off");
+        assert_eq!(video_name("{replay_stem}!", &replay_path), "cut off!");
+    }
+
+    #[test]
+    fn names_are_usable_as_file_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay_path = dir.path().join("replay.osr");
+
+        fs::write(&replay_path, fixture(72)).unwrap();
+
+        assert_eq!(
+            video_name("{player}: a/b\\c <?> \"*\" |", &replay_path),
+            "peppy_ a_b_c ___ ___ _"
+        );
+        assert_eq!(video_name("CON", &replay_path), "_CON");
+        assert_eq!(video_name("{player}. . ", &replay_path), "peppy");
+    }
+
+    #[test]
+    fn numbers_names_that_are_taken() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join("peppy.mp4"), "").unwrap();
+        fs::write(dir.path().join("cookiezi.mkv"), "").unwrap();
+        fs::write(dir.path().join("cookiezi (2).mkv"), "").unwrap();
+
+        let used_names = HashSet::from(["rafis".to_string(), "rafis (2)".to_string()]);
+        let unique = |name: &str| unique_video_name(name.to_string(), &used_names, dir.path());
+
+        assert_eq!(unique("mrekk"), "mrekk");
+        assert_eq!(unique("peppy"), "peppy (2)");
+        assert_eq!(unique("PEPPY"), "PEPPY (2)");
+        assert_eq!(unique("cookiezi"), "cookiezi (3)");
+        assert_eq!(unique("Rafis"), "Rafis (3)");
+        assert_eq!(
+            unique_video_name(
+                "peppy".to_string(),
+                &HashSet::new(),
+                &dir.path().join("missing")
+            ),
+            "peppy"
+        );
+    }
+
+    #[test]
+    fn finds_the_videos_directory_in_the_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut danser = ConfigData::new().danser;
+
+        danser.path = Some(dir.path().to_string_lossy().into_owned());
+
+        assert_eq!(videos_dir(&danser, "default"), dir.path().join("videos"));
+
+        fs::create_dir(dir.path().join("settings")).unwrap();
+        fs::write(
+            dir.path().join("settings").join("streaming.json"),
+            r#"{"Recording": {"OutputDir": "renders"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(videos_dir(&danser, "streaming"), dir.path().join("renders"));
+    }
+}
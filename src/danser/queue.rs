@@ -0,0 +1,398 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{process::Child, sync::Semaphore, task::JoinHandle};
+
+use crate::{
+    config::data::{ProcessState, RenderOptions},
+    launch::output::{spawn_logged, AppLog},
+    util::process::{describe_exit_status, kill_process},
+};
+
+pub enum JobStatus {
+    /// Waiting for a render to finish before it starts.
+    Queued,
+    Rendering,
+    Done,
+    /// The render could not start, failed or timed out, for the given reason.
+    Failed(String),
+}
+
+/// A replay to render.
+pub struct RenderJob {
+    pub replay: PathBuf,
+    /// The hash of the replay's contents, so the same replay is not rendered twice.
+    hash: u64,
+    pub status: JobStatus,
+    /// How the render's process exited, once it has.
+    pub exit_status: Option<ExitStatus>,
+}
+
+impl RenderJob {
+    pub fn name(&self) -> String {
+        file_name(&self.replay)
+    }
+}
+
+/// Renders replays one after the other, or a few at a time, so renders do not slow down the
+/// machine while osu! is being played.
+pub struct RenderQueue {
+    jobs: Arc<Mutex<Vec<RenderJob>>>,
+    permits: Arc<Semaphore>,
+    timeout: Option<Duration>,
+    log: Arc<AppLog>,
+    state: Arc<ProcessState>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl RenderQueue {
+    pub fn new(options: &RenderOptions, log: Arc<AppLog>, state: Arc<ProcessState>) -> RenderQueue {
+        RenderQueue {
+            jobs: Arc::new(Mutex::new(vec![])),
+            permits: Arc::new(Semaphore::new(options.max_parallel.max(1))),
+            timeout: match options.timeout {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+            log,
+            state,
+            tasks: vec![],
+        }
+    }
+
+    /// Queues a render of the replay, unless the same replay is already queued, rendering or
+    /// rendered. The render's command is only built once the replay is known to be queued, so
+    /// building it can reserve things like the video's name.
+    pub fn submit(&mut self, replay: &Path, command: impl FnOnce() -> Option<Command>) {
+        let name = file_name(replay);
+        let hash = match content_hash(replay) {
+            Ok(hash) => hash,
+            Err(error) => {
+                println!("Not rendering replay {}: {}", name, error);
+
+                return;
+            }
+        };
+
+        {
+            let jobs = self.jobs.lock().unwrap();
+
+            let duplicate = jobs
+                .iter()
+                .find(|job| job.hash == hash && !matches!(job.status, JobStatus::Failed(_)));
+
+            if let Some(duplicate) = duplicate {
+                println!(
+                    "Not rendering replay {}: it is the same replay as {}",
+                    name,
+                    duplicate.name()
+                );
+
+                return;
+            }
+        }
+
+        let Some(command) = command() else {
+            return;
+        };
+
+        let position = {
+            let mut jobs = self.jobs.lock().unwrap();
+
+            jobs.push(RenderJob {
+                replay: replay.to_path_buf(),
+                hash,
+                status: JobStatus::Queued,
+                exit_status: None,
+            });
+
+            jobs.len() - 1
+        };
+
+        if self.permits.available_permits() == 0 {
+            println!("Queued replay {}, it renders once a render finishes", name);
+        }
+
+        self.tasks.retain(|task| !task.is_finished());
+        self.tasks.push(tokio::spawn(run_job(
+            position,
+            command,
+            self.jobs.clone(),
+            self.permits.clone(),
+            self.timeout,
+            self.log.clone(),
+            self.state.clone(),
+        )));
+    }
+
+    /// Waits for the queue's renders to finish, and returns every job.
+    pub async fn finish(self) -> Vec<RenderJob> {
+        for task in self.tasks {
+            let _ = task.await;
+        }
+
+        std::mem::take(&mut *self.jobs.lock().unwrap())
+    }
+}
+
+async fn run_job(
+    position: usize,
+    command: Command,
+    jobs: Arc<Mutex<Vec<RenderJob>>>,
+    permits: Arc<Semaphore>,
+    timeout: Option<Duration>,
+    log: Arc<AppLog>,
+    state: Arc<ProcessState>,
+) {
+    let set_status = |status: JobStatus, exit_status: Option<ExitStatus>| {
+        let mut jobs = jobs.lock().unwrap();
+
+        jobs[position].status = status;
+        jobs[position].exit_status = exit_status;
+    };
+    let name = jobs.lock().unwrap()[position].name();
+
+    // Queued renders do not start once Danser is asked to stop
+    let _permit = tokio::select! {
+        permit = permits.acquire() => permit.unwrap(),
+        _ = state.stop.cancelled() => {
+            set_status(JobStatus::Failed("Danser stopped before it started".to_string()), None);
+
+            return;
+        }
+    };
+
+    println!("Rendering replay: {}", name);
+
+    let render = match spawn_logged(&command, &log) {
+        Ok(render) => render,
+        Err(error) => {
+            println!("Failed to launch Danser: {}", error);
+            set_status(JobStatus::Failed(error.to_string()), None);
+
+            return;
+        }
+    };
+
+    set_status(JobStatus::Rendering, None);
+
+    let (status, exit_status) = wait_for_render(render, timeout, &state).await;
+
+    match &status {
+        JobStatus::Done => println!("Rendered replay {}", name),
+        JobStatus::Failed(reason) => println!("Render of replay {} failed: {}", name, reason),
+        _ => (),
+    }
+
+    set_status(status, exit_status);
+}
+
+/// Waits for a render to exit, with its process stopped along with Danser meanwhile. Renders
+/// still running after the timeout are killed.
+async fn wait_for_render(
+    mut render: Child,
+    timeout: Option<Duration>,
+    state: &ProcessState,
+) -> (JobStatus, Option<ExitStatus>) {
+    let Some(pid) = render.id() else {
+        return (
+            JobStatus::Failed("the render exited straight away".to_string()),
+            None,
+        );
+    };
+
+    state.helper_pids.lock().unwrap().push(pid);
+
+    let exited = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, render.wait()).await.ok(),
+        None => Some(render.wait().await),
+    };
+
+    let result = match exited {
+        Some(Ok(exit_status)) if exit_status.success() => (JobStatus::Done, Some(exit_status)),
+        Some(Ok(exit_status)) if state.stop.is_cancelled() => (
+            JobStatus::Failed("stopped along with Danser".to_string()),
+            Some(exit_status),
+        ),
+        Some(Ok(exit_status)) => (
+            JobStatus::Failed(describe_exit_status(&exit_status)),
+            Some(exit_status),
+        ),
+        Some(Err(error)) => (JobStatus::Failed(error.to_string()), None),
+        None => {
+            // Renders run in their own process group, which is killed with them
+            let _ = kill_process(pid, true);
+            let exit_status = render.wait().await.ok();
+
+            (
+                JobStatus::Failed(format!(
+                    "timed out after {} seconds",
+                    timeout.unwrap_or_default().as_secs()
+                )),
+                exit_status,
+            )
+        }
+    };
+
+    state
+        .helper_pids
+        .lock()
+        .unwrap()
+        .retain(|helper_pid| *helper_pid != pid);
+
+    result
+}
+
+pub fn print_jobs(jobs: &[RenderJob]) {
+    if jobs.is_empty() {
+        return;
+    }
+
+    println!("Danser renders:");
+
+    for job in jobs {
+        let status = match &job.status {
+            JobStatus::Queued => "queued".to_string(),
+            JobStatus::Rendering => "rendering".to_string(),
+            JobStatus::Done => match &job.exit_status {
+                Some(exit_status) => format!("done, {}", describe_exit_status(exit_status)),
+                None => "done".to_string(),
+            },
+            JobStatus::Failed(reason) => format!("failed, {}", reason),
+        };
+
+        println!("  {}: {}", job.name(), status);
+    }
+}
+
+fn content_hash(replay: &Path) -> Result<u64, String> {
+    let contents = fs::read(replay).map_err(|error| error.to_string())?;
+    let mut hasher = DefaultHasher::new();
+
+    contents.hash(&mut hasher);
+
+    Ok(hasher.finish())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::data::LogOptions;
+
+    use super::*;
+
+    fn queue(launcher_dir: &Path) -> RenderQueue {
+        RenderQueue::new(
+            &RenderOptions::default(),
+            AppLog::open(
+                "danser",
+                &LogOptions::default(),
+                &launcher_dir.to_string_lossy(),
+            ),
+            Arc::new(ProcessState::default()),
+        )
+    }
+
+    fn exiting_with(code: i32) -> Option<Command> {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+
+            command.arg("/C");
+            command
+        } else {
+            let mut command = Command::new("sh");
+
+            command.arg("-c");
+            command
+        };
+
+        command.arg(format!("exit {}", code));
+
+        Some(command)
+    }
+
+    async fn wait_for_jobs(queue: &RenderQueue) {
+        while !queue.tasks.iter().all(|task| task.is_finished()) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn renders_the_same_replay_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut queue = queue(dir.path());
+
+        fs::write(dir.path().join("first.osr"), b"replay").unwrap();
+        fs::write(dir.path().join("copy.osr"), b"replay").unwrap();
+        fs::write(dir.path().join("other.osr"), b"other replay").unwrap();
+
+        let mut built = 0;
+
+        for replay in ["first.osr", "copy.osr", "first.osr", "other.osr"] {
+            queue.submit(&dir.path().join(replay), || {
+                built += 1;
+
+                exiting_with(0)
+            });
+        }
+
+        let jobs = queue.finish().await;
+        let names: Vec<String> = jobs.iter().map(RenderJob::name).collect();
+
+        // Duplicates are dropped before their command, and so their video's name, is made
+        assert_eq!(built, 2);
+        assert_eq!(names, ["first.osr", "other.osr"]);
+        assert!(jobs.iter().all(|job| matches!(job.status, JobStatus::Done)));
+    }
+
+    #[tokio::test]
+    async fn renders_a_failed_replay_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = dir.path().join("replay.osr");
+        let mut queue = queue(dir.path());
+
+        fs::write(&replay, b"replay").unwrap();
+
+        queue.submit(&replay, || exiting_with(1));
+        wait_for_jobs(&queue).await;
+        queue.submit(&replay, || exiting_with(0));
+
+        let jobs = queue.finish().await;
+
+        assert_eq!(jobs.len(), 2);
+        assert!(matches!(&jobs[0].status, JobStatus::Failed(_)));
+        assert!(matches!(jobs[1].status, JobStatus::Done));
+    }
+
+    #[tokio::test]
+    async fn skips_replays_without_a_command_or_that_cannot_be_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = dir.path().join("replay.osr");
+        let mut queue = queue(dir.path());
+        let mut built = false;
+
+        fs::write(&replay, b"replay").unwrap();
+
+        queue.submit(&dir.path().join("missing.osr"), || {
+            built = true;
+
+            exiting_with(0)
+        });
+        queue.submit(&replay, || None);
+
+        assert!(!built);
+        assert!(queue.finish().await.is_empty());
+    }
+}
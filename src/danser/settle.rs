@@ -0,0 +1,94 @@
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use notify::{
+    event::{AccessKind, AccessMode, CreateKind, ModifyKind, RenameMode},
+    Event, EventKind,
+};
+use tokio::time::sleep;
+
+use crate::{config::data::SettleOptions, replay::Replay};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The replay a file system event says was created, moved into place or finished being written.
+/// Other files, like the temporary files replays are written to, are ignored.
+pub fn written_replay(event: &Event) -> Option<&Path> {
+    let path = match event.kind {
+        EventKind::Create(CreateKind::File | CreateKind::Any)
+        | EventKind::Modify(ModifyKind::Name(RenameMode::To))
+        | EventKind::Access(AccessKind::Close(AccessMode::Write)) => event.paths.first(),
+        // The path the file was renamed to comes second
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => event.paths.get(1),
+        _ => None,
+    }?;
+
+    is_replay_file(path).then_some(path.as_path())
+}
+
+fn is_replay_file(path: &Path) -> bool {
+    let is_hidden = path
+        .file_name()
+        .map(|name| name.to_string_lossy().starts_with('.'))
+        .unwrap_or(true);
+    let is_osr = path
+        .extension()
+        .map(|extension| extension.eq_ignore_ascii_case("osr"))
+        .unwrap_or(false);
+
+    is_osr && !is_hidden
+}
+
+/// Waits until osu! has finished writing the replay: its size has not changed for the settle
+/// interval and it reads as a whole replay. Fails with the reason when that does not happen
+/// within the settle timeout.
+pub async fn wait_until_settled(path: &Path, options: &SettleOptions) -> Result<(), String> {
+    let interval = Duration::try_from_secs_f64(options.interval)
+        .map_err(|_| format!("{} is not a number of seconds", options.interval))?;
+    let timeout = Duration::from_secs(options.timeout);
+    let started = Instant::now();
+    let mut last_size = None;
+    let mut stable_since = Instant::now();
+    let mut last_error = String::new();
+
+    while started.elapsed() < timeout {
+        let Ok(metadata) = fs::metadata(path) else {
+            return Err("it no longer exists".to_string());
+        };
+        let size = Some(metadata.len());
+
+        if size != last_size {
+            last_size = size;
+            stable_since = Instant::now();
+        } else if stable_since.elapsed() >= interval {
+            match fs::read(path).map_err(|error| error.to_string()) {
+                Ok(bytes) => match Replay::parse(&bytes) {
+                    Ok(_) => return Ok(()),
+                    Err(error) => last_error = error.to_string(),
+                },
+                Err(error) => last_error = error,
+            }
+
+            // Check again once it has changed, or after another interval
+            stable_since = Instant::now();
+        }
+
+        sleep(POLL_INTERVAL).await;
+    }
+
+    if last_error.is_empty() {
+        Err(format!(
+            "it kept changing for {} seconds",
+            timeout.as_secs()
+        ))
+    } else {
+        Err(format!(
+            "it was not finished within {} seconds: {}",
+            timeout.as_secs(),
+            last_error
+        ))
+    }
+}
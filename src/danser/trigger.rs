@@ -0,0 +1,320 @@
+use std::path::Path;
+
+use crate::{
+    config::data::{RenderTrigger, ReplayFilter},
+    replay::{mods::mod_bit, Replay},
+    util::{file::path_exists, path::expand_path, win::is_async_key_pressed},
+};
+
+/// Tells whether keys are held, so triggers can be checked without a real keyboard.
+pub trait Keys {
+    fn is_pressed(&self, key: i32) -> bool;
+}
+
+/// The keyboard of the machine the launcher runs on.
+pub struct Keyboard;
+
+impl Keys for Keyboard {
+    fn is_pressed(&self, key: i32) -> bool {
+        is_async_key_pressed(key).unwrap_or(false)
+    }
+}
+
+pub enum TriggerDecision {
+    Render,
+    /// Do not render the replay, for the given reason.
+    Skip(String),
+}
+
+/// Checks the trigger's settings, so mistakes are reported when Danser starts rather than when a
+/// replay is saved.
+pub fn validate(trigger: &RenderTrigger) -> Result<(), String> {
+    match trigger {
+        RenderTrigger::Hotkey { key } => key_code(key).map(|_| ()),
+        RenderTrigger::MarkerFile { path } => expand_path(path).map(|_| ()),
+        RenderTrigger::Filter(filter) => {
+            for acronym in &filter.mods {
+                mod_bit(acronym).ok_or_else(|| format!("{} is not a mod", acronym))?;
+            }
+
+            Ok(())
+        }
+        RenderTrigger::Always | RenderTrigger::Never => Ok(()),
+    }
+}
+
+/// Whether the trigger needs what is in the replay, so it can only be checked once the replay is
+/// written.
+pub fn reads_replay(trigger: &RenderTrigger) -> bool {
+    matches!(trigger, RenderTrigger::Filter(_))
+}
+
+/// Whether the trigger renders the new replay at `replay_path`.
+pub fn should_render(
+    trigger: &RenderTrigger,
+    replay_path: &Path,
+    keys: &dyn Keys,
+) -> TriggerDecision {
+    match trigger {
+        RenderTrigger::Always => TriggerDecision::Render,
+        RenderTrigger::Never => TriggerDecision::Skip("rendering is turned off".to_string()),
+        RenderTrigger::Hotkey { key } => match key_code(key) {
+            Ok(code) if keys.is_pressed(code) => TriggerDecision::Render,
+            Ok(_) => TriggerDecision::Skip(format!("{} was not held", key)),
+            Err(error) => TriggerDecision::Skip(error),
+        },
+        RenderTrigger::MarkerFile { path } => match expand_path(path) {
+            Ok(path) if path_exists(&path) => TriggerDecision::Render,
+            Ok(path) => TriggerDecision::Skip(format!("{} does not exist", path)),
+            Err(error) => TriggerDecision::Skip(error),
+        },
+        RenderTrigger::Filter(filter) => match Replay::read(replay_path) {
+            Ok(replay) => match filter_mismatch(filter, &replay) {
+                Some(reason) => TriggerDecision::Skip(reason),
+                None => TriggerDecision::Render,
+            },
+            Err(error) => TriggerDecision::Skip(error.to_string()),
+        },
+    }
+}
+
+/// Why the replay does not match the filter, if it does not.
+fn filter_mismatch(filter: &ReplayFilter, replay: &Replay) -> Option<String> {
+    if !filter.modes.is_empty() && !filter.modes.contains(&replay.mode) {
+        return Some(format!("it was played in {}", replay.mode));
+    }
+
+    for acronym in &filter.mods {
+        match mod_bit(acronym) {
+            Some(bit) if replay.mods & bit != 0 => (),
+            _ => {
+                return Some(format!(
+                    "it was not played with {}",
+                    acronym.to_ascii_uppercase()
+                ))
+            }
+        }
+    }
+
+    if !filter.players.is_empty()
+        && !filter
+            .players
+            .iter()
+            .any(|player| player.eq_ignore_ascii_case(&replay.player))
+    {
+        return Some(format!("it was played by {}", replay.player));
+    }
+
+    None
+}
+
+/// The virtual key code of a key like `R`, `5`, `F5` or `Space`, or of a key code like `0x52`.
+pub fn key_code(key: &str) -> Result<i32, String> {
+    let invalid = || format!("{} is not a key", key);
+    let upper = key.to_ascii_uppercase();
+
+    // Letters and digits have their ASCII code
+    if let [character] = upper.as_bytes() {
+        if character.is_ascii_alphanumeric() {
+            return Ok(*character as i32);
+        }
+    }
+
+    let code = if let Some(hex) = upper.strip_prefix("0X") {
+        i32::from_str_radix(hex, 16).ok()
+    } else if let Some(number) = upper.strip_prefix('F') {
+        match number.parse::<i32>() {
+            Ok(number) if (1..=24).contains(&number) => Some(0x6f + number),
+            _ => None,
+        }
+    } else {
+        match upper.as_str() {
+            "TAB" => Some(0x09),
+            "SHIFT" => Some(0x10),
+            "CTRL" | "CONTROL" => Some(0x11),
+            "ALT" => Some(0x12),
+            "SPACE" => Some(0x20),
+            _ => upper.parse::<i32>().ok(),
+        }
+    };
+
+    code.filter(|code| (1..=254).contains(code))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::replay::{tests::fixture, GameMode};
+
+    // Holds the keys it was made with
+    struct FakeKeys(Vec<i32>);
+
+    impl Keys for FakeKeys {
+        fn is_pressed(&self, key: i32) -> bool {
+            self.0.contains(&key)
+        }
+    }
+
+    // The fixture replay is played by peppy with HD and DT
+    fn write_replay(dir: &Path, mode: u8) -> PathBuf {
+        let path = dir.join("replay.osr");
+        let mut bytes = fixture(72);
+
+        bytes[0] = mode;
+        fs::write(&path, bytes).unwrap();
+
+        path
+    }
+
+    fn skip_reason(decision: TriggerDecision) -> Option<String> {
+        match decision {
+            TriggerDecision::Render => None,
+            TriggerDecision::Skip(reason) => Some(reason),
+        }
+    }
+
+    #[test]
+    fn hotkey_renders_while_held() {
+        let trigger = RenderTrigger::Hotkey {
+            key: "f5".to_string(),
+        };
+        let replay = Path::new("unused.osr");
+
+        assert_eq!(
+            skip_reason(should_render(&trigger, replay, &FakeKeys(vec![0x74]))),
+            None
+        );
+        assert_eq!(
+            skip_reason(should_render(&trigger, replay, &FakeKeys(vec![0x52]))),
+            Some("f5 was not held".to_string())
+        );
+    }
+
+    #[test]
+    fn marker_file_renders_while_it_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("render-marker");
+        let trigger = RenderTrigger::MarkerFile {
+            path: marker.to_string_lossy().into_owned(),
+        };
+        let replay = Path::new("unused.osr");
+
+        assert_eq!(
+            skip_reason(should_render(&trigger, replay, &FakeKeys(vec![]))),
+            Some(format!("{} does not exist", marker.to_string_lossy()))
+        );
+
+        fs::write(&marker, "").unwrap();
+        assert_eq!(
+            skip_reason(should_render(&trigger, replay, &FakeKeys(vec![]))),
+            None
+        );
+    }
+
+    #[test]
+    fn filter_renders_matching_replays() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = write_replay(dir.path(), 0);
+        let trigger = RenderTrigger::Filter(ReplayFilter {
+            modes: vec![GameMode::Osu, GameMode::Taiko],
+            mods: vec!["hd".to_string(), "DT".to_string()],
+            players: vec!["PEPPY".to_string()],
+        });
+
+        assert_eq!(
+            skip_reason(should_render(&trigger, &replay, &FakeKeys(vec![]))),
+            None
+        );
+    }
+
+    #[test]
+    fn filter_skips_replays_that_do_not_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = write_replay(dir.path(), 3);
+        let cases = [
+            (
+                ReplayFilter {
+                    modes: vec![GameMode::Osu],
+                    ..Default::default()
+                },
+                "it was played in osu!mania",
+            ),
+            (
+                ReplayFilter {
+                    mods: vec!["HD".to_string(), "hr".to_string()],
+                    ..Default::default()
+                },
+                "it was not played with HR",
+            ),
+            (
+                ReplayFilter {
+                    players: vec!["cookiezi".to_string()],
+                    ..Default::default()
+                },
+                "it was played by peppy",
+            ),
+        ];
+
+        for (filter, reason) in cases {
+            let trigger = RenderTrigger::Filter(filter);
+
+            assert_eq!(
+                skip_reason(should_render(&trigger, &replay, &FakeKeys(vec![]))),
+                Some(reason.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn filter_skips_replays_it_cannot_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = dir.path().join("truncated.osr");
+
+        fs::write(&replay, &fixture(72)[..20]).unwrap();
+
+        let trigger = RenderTrigger::Filter(ReplayFilter::default());
+
+        assert!(skip_reason(should_render(&trigger, &replay, &FakeKeys(vec![]))).is_some());
+    }
+
+    #[test]
+    fn key_codes() {
+        let cases = [
+            ("r", Ok(0x52)),
+            ("5", Ok(0x35)),
+            ("F1", Ok(0x70)),
+            ("f24", Ok(0x87)),
+            ("Space", Ok(0x20)),
+            ("control", Ok(0x11)),
+            ("0x52", Ok(0x52)),
+            ("82", Ok(82)),
+            ("F25", Err("F25 is not a key".to_string())),
+            ("0x100", Err("0x100 is not a key".to_string())),
+            ("0", Ok(0x30)),
+            ("", Err(" is not a key".to_string())),
+            ("enter", Err("enter is not a key".to_string())),
+        ];
+
+        for (key, code) in cases {
+            assert_eq!(key_code(key), code, "{}", key);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unknown_keys_and_mods() {
+        assert!(validate(&RenderTrigger::Hotkey {
+            key: "enter".to_string()
+        })
+        .is_err());
+        assert_eq!(
+            validate(&RenderTrigger::Filter(ReplayFilter {
+                mods: vec!["HD".to_string(), "XX".to_string()],
+                ..Default::default()
+            })),
+            Err("XX is not a mod".to_string())
+        );
+    }
+}
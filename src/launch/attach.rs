@@ -0,0 +1,44 @@
+use std::{
+    process::ExitStatus,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use tokio::time::sleep;
+
+use crate::{
+    config::data::{AppProcess, ProcessState},
+    util::process::process_exists,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches a process the launcher did not start, so it can be closed with the other applications.
+/// Its exit code cannot be known, so it is reported as a success.
+pub fn attach(name: String, pid: u32) -> AppProcess {
+    let state = Arc::new(ProcessState::default());
+
+    state.pid.store(pid, Ordering::Relaxed);
+    state.attached.store(true, Ordering::Relaxed);
+
+    let watcher_state = state.clone();
+    let handle = tokio::spawn(async move {
+        // Checking for the process blocks, off Linux it lists every process
+        while tokio::task::spawn_blocking(move || process_exists(pid))
+            .await
+            .unwrap_or(false)
+        {
+            sleep(POLL_INTERVAL).await;
+        }
+
+        watcher_state.pid.store(0, Ordering::Relaxed);
+
+        Ok(ExitStatus::default())
+    });
+
+    AppProcess {
+        name,
+        state,
+        handle,
+    }
+}
@@ -0,0 +1,256 @@
+use serde::Serialize;
+
+use super::{plan::start_order, ready, runner};
+use crate::{
+    config::{
+        data::{
+            ConfigData, IfRunning, LaunchContext, ResolvedLaunchOptions, RestartPolicy, Runner,
+        },
+        traits::app_data::Application,
+    },
+    util::process::find_executable_processes,
+};
+
+/// What the launcher would do with the current config, worked out without downloading or
+/// starting anything.
+#[derive(Serialize)]
+pub struct LaunchPlan {
+    pub downloads: Vec<PlannedDownload>,
+    pub applications: Vec<PlannedApplication>,
+    /// The order the applications would start in, empty when it could not be worked out.
+    pub start_order: Vec<String>,
+    /// Why the start order could not be worked out.
+    pub start_order_error: Option<String>,
+    /// The directory Danser would watch for new replays.
+    pub danser_watch_dir: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlannedDownload {
+    pub application: String,
+    pub url: String,
+    /// The directory the downloaded zip would be extracted to.
+    pub extract_to: String,
+}
+
+#[derive(Serialize)]
+pub struct PlannedApplication {
+    pub name: String,
+    pub executable_path: Option<String>,
+    pub executable_exists: bool,
+    pub runner: Runner,
+    /// The `args`, `env` and `cwd` with the placeholders in them replaced.
+    #[serde(flatten)]
+    pub launch: Option<ResolvedLaunchOptions>,
+    pub depends_on: Vec<String>,
+    pub ready: Option<String>,
+    pub if_running: IfRunning,
+    /// The process id of a copy of the application that is already running.
+    pub already_running: Option<u32>,
+    pub restart: RestartPolicy,
+    /// Why the application could not be started as configured.
+    pub errors: Vec<String>,
+}
+
+impl LaunchPlan {
+    pub fn new(config: &ConfigData) -> LaunchPlan {
+        let context = LaunchContext::new(config);
+        let configured_names: Vec<String> = config
+            .applications()
+            .iter()
+            .map(|application| application.get_name())
+            .collect();
+        let enabled_applications: Vec<&dyn Application> = config
+            .applications()
+            .into_iter()
+            .filter(|application| application.get_enabled())
+            .collect();
+
+        let downloads = enabled_applications
+            .iter()
+            .filter(|application| application.can_download())
+            .filter_map(|application| {
+                Some(PlannedDownload {
+                    application: application.get_name(),
+                    url: application.get_public_download_url()?,
+                    extract_to: application.get_expanded_path().ok()?,
+                })
+            })
+            .collect();
+
+        let mut applications: Vec<PlannedApplication> = enabled_applications
+            .iter()
+            .map(|application| plan_application(*application, &context))
+            .collect();
+
+        let (start_order, start_order_error) =
+            match start_order(&enabled_applications, &configured_names) {
+                Ok(order) => (
+                    order
+                        .iter()
+                        .map(|application| application.get_name())
+                        .collect(),
+                    None,
+                ),
+                Err(error) => (vec![], Some(error.to_string())),
+            };
+
+        let danser_watch_dir = match config.danser.get_replays_path() {
+            _ if !config.danser.get_enabled() => None,
+            Ok(path) => Some(path.to_string_lossy().into_owned()),
+            Err(error) => {
+                let danser_name = config.danser.get_name();
+                let danser = applications
+                    .iter_mut()
+                    .find(|application| application.name == danser_name);
+
+                if let Some(danser) = danser {
+                    danser.errors.push(format!("Replays directory: {}", error));
+                }
+
+                None
+            }
+        };
+
+        LaunchPlan {
+            downloads,
+            applications,
+            start_order,
+            start_order_error,
+            danser_watch_dir,
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Dry run, nothing will be downloaded or started");
+        println!();
+
+        if self.downloads.is_empty() {
+            println!("Downloads: none");
+        } else {
+            println!("Downloads:");
+
+            for download in &self.downloads {
+                println!(
+                    "  {} from {}, extracted to {}",
+                    download.application, download.url, download.extract_to
+                );
+            }
+        }
+
+        match &self.start_order_error {
+            Some(error) => println!("Start order: {}", error),
+            None => println!("Start order: {}", self.start_order.join(", ")),
+        }
+
+        for application in &self.applications {
+            println!();
+            println!("{}", application.name);
+
+            if let Some(executable_path) = &application.executable_path {
+                let downloaded = self
+                    .downloads
+                    .iter()
+                    .any(|download| download.application == application.name);
+                let note = match (application.executable_exists, downloaded) {
+                    (true, _) => "",
+                    (false, true) => " (after it is downloaded)",
+                    (false, false) => " (not found)",
+                };
+
+                println!("  executable: {}{}", executable_path, note);
+            }
+
+            if application.runner != Runner::Native {
+                println!("  runner: {}", runner::describe(&application.runner));
+            }
+
+            if let Some(launch) = &application.launch {
+                if !launch.args.is_empty() {
+                    println!("  args: {}", launch.args.join(" "));
+                }
+
+                for (name, value) in &launch.env {
+                    println!("  env: {}={}", name, value);
+                }
+
+                if let Some(cwd) = &launch.cwd {
+                    println!("  cwd: {}", cwd);
+                }
+            }
+
+            if !application.depends_on.is_empty() {
+                println!("  depends on: {}", application.depends_on.join(", "));
+            }
+
+            if let Some(ready) = &application.ready {
+                println!("  ready: {}", ready);
+            }
+
+            if let Some(pid) = application.already_running {
+                let action = match application.if_running {
+                    IfRunning::Skip => "it would not be started again",
+                    IfRunning::Attach => "it would be watched instead of started",
+                    IfRunning::LaunchAnyway => "another copy would be started",
+                };
+
+                println!("  already running (process {}), {}", pid, action);
+            }
+
+            for error in &application.errors {
+                println!("  error: {}", error);
+            }
+        }
+
+        if let Some(danser_watch_dir) = &self.danser_watch_dir {
+            println!();
+            println!("Danser would watch {} for new replays", danser_watch_dir);
+        }
+    }
+}
+
+fn plan_application(application: &dyn Application, context: &LaunchContext) -> PlannedApplication {
+    let launch_options = application.get_launch_options();
+    let mut errors = vec![];
+
+    let executable_path = match application.get_executable_path() {
+        Ok(path) => Some(path.to_string_lossy().into_owned()),
+        Err(error) => {
+            errors.push(error.to_string());
+
+            None
+        }
+    };
+
+    let launch = match application.resolve_launch_options(context) {
+        Ok(launch) => Some(launch),
+        Err(error) => {
+            errors.push(error.to_string());
+
+            None
+        }
+    };
+
+    let already_running = match launch_options.if_running {
+        IfRunning::LaunchAnyway => None,
+        _ => application.get_running_executable().and_then(|executable| {
+            find_executable_processes(&executable)
+                .first()
+                .map(|process| process.pid)
+        }),
+    };
+
+    PlannedApplication {
+        name: application.get_name(),
+        executable_path,
+        executable_exists: application.executable_exists(),
+        runner: application.get_runner(context).clone(),
+        launch,
+        depends_on: launch_options.depends_on.clone(),
+        ready: launch_options.ready.as_ref().map(ready::describe),
+        if_running: launch_options.if_running,
+        already_running,
+        restart: launch_options.restart.policy,
+        errors,
+    }
+}
@@ -0,0 +1,174 @@
+pub mod attach;
+pub mod dry_run;
+pub mod output;
+pub mod plan;
+pub mod ready;
+pub mod restart;
+pub mod runner;
+pub mod shutdown;
+pub mod signals;
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use futures::future::{join_all, BoxFuture, FutureExt, Shared};
+
+use crate::{
+    config::{
+        data::{AppProcess, ConfigData, IfRunning, LaunchContext},
+        error::launch_error::LaunchError,
+        traits::app_data::Application,
+    },
+    util::process::find_executable_processes,
+};
+
+use attach::attach;
+use plan::start_order;
+use ready::{describe, wait_until_ready};
+
+type Readiness<'a> = Shared<BoxFuture<'a, bool>>;
+
+/// Starts the enabled applications, each one once the applications it depends on are ready.
+/// Applications that do not depend on each other start at the same time. The started applications
+/// are returned in the order they started.
+pub async fn start_applications(config: &ConfigData) -> Result<Vec<AppProcess>, LaunchError> {
+    let configured_names: Vec<String> = config
+        .applications()
+        .iter()
+        .map(|application| application.get_name())
+        .collect();
+    let enabled_applications: Vec<&dyn Application> = config
+        .applications()
+        .into_iter()
+        .filter(|application| application.get_enabled())
+        .collect();
+
+    let order = start_order(&enabled_applications, &configured_names)?;
+
+    println!(
+        "Start order: {}",
+        order
+            .iter()
+            .map(|application| application.get_name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let context = LaunchContext::new(config);
+    let started = Mutex::new(vec![]);
+    let mut readiness: HashMap<String, Readiness> = HashMap::new();
+    let mut starts = vec![];
+
+    for application in order {
+        // Dependencies on disabled applications have no entry and are not waited for
+        let dependencies: Vec<(String, Readiness)> = application
+            .get_launch_options()
+            .depends_on
+            .iter()
+            .filter_map(|dependency| {
+                let ready = readiness.get(dependency)?.clone();
+
+                Some((dependency.clone(), ready))
+            })
+            .collect();
+
+        let ready = start_when_ready(application, &context, dependencies, &started)
+            .boxed()
+            .shared();
+
+        readiness.insert(application.get_name(), ready.clone());
+        starts.push(ready);
+    }
+
+    // Polled in start order, so applications that are ready to start together start in order
+    join_all(starts).await;
+    drop(readiness);
+
+    Ok(started.into_inner().unwrap())
+}
+
+/// Starts an application once its dependencies are ready, and resolves to whether it became
+/// ready itself.
+async fn start_when_ready(
+    application: &dyn Application,
+    context: &LaunchContext,
+    dependencies: Vec<(String, Readiness<'_>)>,
+    started: &Mutex<Vec<AppProcess>>,
+) -> bool {
+    let name = application.get_name();
+
+    for (dependency, ready) in dependencies {
+        if !ready.await {
+            println!(
+                "Not starting {} because {} did not start or is not ready",
+                name, dependency
+            );
+
+            return false;
+        }
+    }
+
+    let launch_options = application.get_launch_options();
+    let running_executable = match launch_options.if_running {
+        IfRunning::LaunchAnyway => None,
+        _ => application.get_running_executable(),
+    };
+    // Listing the processes blocks, on Windows it runs tasklist
+    let running_process = match running_executable {
+        Some(executable) => tokio::task::spawn_blocking(move || {
+            find_executable_processes(&executable).into_iter().next()
+        })
+        .await
+        .ok()
+        .flatten(),
+        None => None,
+    };
+
+    match running_process {
+        Some(running_process) if launch_options.if_running == IfRunning::Attach => {
+            println!(
+                "{} is already running (process {}), watching it instead of starting it again",
+                name, running_process.pid
+            );
+
+            started
+                .lock()
+                .unwrap()
+                .push(attach(name.clone(), running_process.pid));
+        }
+        Some(running_process) => println!(
+            "{} is already running (process {}), not starting it again",
+            name, running_process.pid
+        ),
+        None => match application.try_spawn_process(context) {
+            Ok(process) => started.lock().unwrap().push(process),
+            Err(error) => {
+                println!("Error spawning process {}: {} - skipping", name, error);
+
+                return false;
+            }
+        },
+    }
+
+    let Some(condition) = &launch_options.ready else {
+        return true;
+    };
+
+    println!(
+        "Waiting for {} to be ready, {}...",
+        name,
+        describe(condition)
+    );
+
+    match wait_until_ready(condition, Duration::from_secs(launch_options.ready_timeout)).await {
+        Ok(_) => {
+            println!("{} is ready", name);
+
+            true
+        }
+        Err(reason) => {
+            println!("{} is {}", name, reason);
+
+            false
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+#[cfg(windows)]
+use crate::util::process::add_to_launcher_job;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Child,
+};
+
+use crate::{
+    config::data::LogOptions,
+    util::{
+        log::LogFile,
+        process::{clone_command, isolate_child},
+    },
+};
+
+/// Where an application's output goes: its log file, and the console if the application is set to
+/// mirror it there.
+pub struct AppLog {
+    name: String,
+    file: Option<Mutex<LogFile>>,
+    timestamps: bool,
+    console: bool,
+}
+
+impl AppLog {
+    /// Opens `<launcher_dir>/logs/<name>.log`. If the log file cannot be opened, the output goes
+    /// to the console instead.
+    pub fn open(name: &str, options: &LogOptions, launcher_dir: &str) -> Arc<AppLog> {
+        let path = Path::new(launcher_dir)
+            .join("logs")
+            .join(format!("{}.log", file_name_safe(name)));
+
+        // A size too large to count in bytes is as good as no limit
+        let max_size = options.max_size_mb.saturating_mul(1024 * 1024);
+
+        let file = match LogFile::open(&path, max_size, options.max_files) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(error) => {
+                println!(
+                    "Could not open log file {}: {} - showing the output of {} on the console",
+                    path.display(),
+                    error,
+                    name
+                );
+
+                None
+            }
+        };
+
+        Arc::new(AppLog {
+            name: name.to_string(),
+            console: options.console || file.is_none(),
+            file,
+            timestamps: options.timestamps,
+        })
+    }
+
+    fn write_line(&self, line: &str, is_error: bool) {
+        if let Some(file) = &self.file {
+            let line = if self.timestamps {
+                format!(
+                    "{} {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                    line
+                )
+            } else {
+                line.to_string()
+            };
+
+            if let Ok(mut file) = file.lock() {
+                let _ = file.write_line(&line);
+            }
+        }
+
+        if self.console {
+            if is_error {
+                eprintln!("[{}] {}", self.name, line);
+            } else {
+                println!("[{}] {}", self.name, line);
+            }
+        }
+    }
+}
+
+/// Spawns a command with its output going to `log`. The command is copied, so it can be spawned
+/// again on restart.
+pub fn spawn_logged(command: &Command, log: &Arc<AppLog>) -> io::Result<Child> {
+    let mut command = clone_command(command);
+
+    isolate_child(&mut command);
+
+    let mut child = tokio::process::Command::from(command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    #[cfg(windows)]
+    if let Err(error) = add_to_launcher_job(&child) {
+        println!(
+            "Could not tie {} to the launcher, it may keep running if the launcher crashes: {}",
+            log.name, error
+        );
+    }
+
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(forward_lines(stdout, log.clone(), false));
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(forward_lines(stderr, log.clone(), true));
+    }
+
+    Ok(child)
+}
+
+async fn forward_lines(output: impl AsyncRead + Unpin, log: Arc<AppLog>, is_error: bool) {
+    let mut reader = BufReader::new(output);
+    let mut line = vec![];
+
+    // Lines are read as bytes, as not every program writes UTF-8
+    while let Ok(length) = reader.read_until(b'\n', &mut line).await {
+        if length == 0 {
+            break;
+        }
+
+        let text = String::from_utf8_lossy(&line);
+
+        log.write_line(text.trim_end_matches(['\r', '\n']), is_error);
+        line.clear();
+    }
+}
+
+/// Replaces characters that cannot be in a file name, as custom app names can be anything.
+fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_logs_too_large_to_count_in_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = LogOptions {
+            max_size_mb: u64::MAX,
+            ..LogOptions::default()
+        };
+
+        let log = AppLog::open("osu", &options, &dir.path().to_string_lossy());
+
+        assert!(log.file.is_some());
+        assert!(dir.path().join("logs").join("osu.log").exists());
+    }
+}
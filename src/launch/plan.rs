@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::config::{error::launch_error::LaunchError, traits::app_data::Application};
+
+/// Orders applications so each one comes after the applications it depends on. Dependencies on
+/// applications missing from `applications`, because they are disabled, are ignored, as long as
+/// they are configured at all.
+pub fn start_order<'a>(
+    applications: &[&'a dyn Application],
+    configured_names: &[String],
+) -> Result<Vec<&'a dyn Application>, LaunchError> {
+    let positions: HashMap<String, usize> = applications
+        .iter()
+        .enumerate()
+        .map(|(position, application)| (application.get_name(), position))
+        .collect();
+
+    let mut dependencies: Vec<Vec<usize>> = vec![];
+
+    for application in applications {
+        let mut application_dependencies = vec![];
+
+        for dependency in &application.get_launch_options().depends_on {
+            match positions.get(dependency) {
+                Some(position) => application_dependencies.push(*position),
+                None if configured_names.contains(dependency) => (),
+                None => {
+                    return Err(LaunchError::UnknownDependency {
+                        app: application.get_name(),
+                        dependency: dependency.clone(),
+                    })
+                }
+            }
+        }
+
+        dependencies.push(application_dependencies);
+    }
+
+    // Depth-first search, keeping the configured order for applications that do not depend on
+    // each other
+    let mut order = vec![];
+    let mut states = vec![VisitState::New; applications.len()];
+
+    for position in 0..applications.len() {
+        visit(
+            position,
+            &dependencies,
+            &mut states,
+            &mut vec![],
+            &mut order,
+        )
+        .map_err(|cycle| {
+            LaunchError::DependencyCycle(
+                cycle
+                    .into_iter()
+                    .map(|position| applications[position].get_name())
+                    .collect(),
+            )
+        })?;
+    }
+
+    Ok(order
+        .into_iter()
+        .map(|position| applications[position])
+        .collect())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    New,
+    Visiting,
+    Done,
+}
+
+fn visit(
+    position: usize,
+    dependencies: &[Vec<usize>],
+    states: &mut [VisitState],
+    path: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) -> Result<(), Vec<usize>> {
+    match states[position] {
+        VisitState::Done => return Ok(()),
+        VisitState::Visiting => {
+            let cycle_start = path.iter().position(|item| *item == position).unwrap();
+            let mut cycle = path[cycle_start..].to_vec();
+
+            cycle.push(position);
+
+            return Err(cycle);
+        }
+        VisitState::New => (),
+    }
+
+    states[position] = VisitState::Visiting;
+    path.push(position);
+
+    for dependency in &dependencies[position] {
+        visit(*dependency, dependencies, states, path, order)?;
+    }
+
+    path.pop();
+    states[position] = VisitState::Done;
+    order.push(position);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::data::{CustomAppData, LaunchOptions};
+
+    fn app(name: &str, depends_on: &[&str]) -> CustomAppData {
+        CustomAppData {
+            name: name.to_string(),
+            path: None,
+            executable_name: None,
+            source: None,
+            enabled: true,
+            launch: LaunchOptions {
+                depends_on: depends_on.iter().map(|name| name.to_string()).collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn order(apps: &[CustomAppData], configured: &[&str]) -> Result<Vec<String>, LaunchError> {
+        let applications: Vec<&dyn Application> =
+            apps.iter().map(|app| app as &dyn Application).collect();
+        let configured_names: Vec<String> =
+            configured.iter().map(|name| name.to_string()).collect();
+
+        start_order(&applications, &configured_names).map(|order| {
+            order
+                .into_iter()
+                .map(|application| application.get_name())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn dependencies_start_first() {
+        let apps = [
+            app("osu!", &["tosu", "OpenTabletDriver"]),
+            app("tosu", &[]),
+            app("overlay", &["tosu"]),
+            app("OpenTabletDriver", &[]),
+        ];
+        let names = ["osu!", "tosu", "overlay", "OpenTabletDriver"];
+
+        assert_eq!(
+            order(&apps, &names).unwrap(),
+            ["tosu", "OpenTabletDriver", "osu!", "overlay"]
+        );
+    }
+
+    #[test]
+    fn disabled_dependencies_are_ignored() {
+        let apps = [app("osu!", &["OpenTabletDriver"])];
+
+        assert_eq!(
+            order(&apps, &["osu!", "OpenTabletDriver"]).unwrap(),
+            ["osu!"]
+        );
+    }
+
+    #[test]
+    fn unknown_dependencies_fail() {
+        let apps = [app("osu!", &["tosu"]), app("tosu", &["gosumemory"])];
+
+        assert!(matches!(
+            order(&apps, &["osu!", "tosu"]),
+            Err(LaunchError::UnknownDependency { app, dependency })
+                if app == "tosu" && dependency == "gosumemory"
+        ));
+    }
+
+    #[test]
+    fn cycles_fail() {
+        let apps = [
+            app("osu!", &["tosu"]),
+            app("tosu", &["overlay"]),
+            app("overlay", &["osu!"]),
+        ];
+
+        assert!(matches!(
+            order(&apps, &["osu!", "tosu", "overlay"]),
+            Err(LaunchError::DependencyCycle(cycle))
+                if cycle == ["osu!", "tosu", "overlay", "osu!"]
+        ));
+    }
+
+    #[test]
+    fn depending_on_itself_is_a_cycle() {
+        let apps = [app("osu!", &["osu!"])];
+
+        assert!(matches!(
+            order(&apps, &["osu!"]),
+            Err(LaunchError::DependencyCycle(cycle)) if cycle == ["osu!", "osu!"]
+        ));
+    }
+}
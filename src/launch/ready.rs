@@ -0,0 +1,67 @@
+use std::{path::Path, time::Duration};
+
+use tokio::{net::TcpStream, time::sleep};
+
+use crate::{
+    config::data::ReadyCondition,
+    util::{path::expand_path, process::find_processes},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Waits until an application meets its ready condition, or fails with the reason it did not
+/// within `timeout`.
+pub async fn wait_until_ready(condition: &ReadyCondition, timeout: Duration) -> Result<(), String> {
+    if let ReadyCondition::Delay(seconds) = condition {
+        // Delays are checked when the config is loaded, but one that is not a Duration must not
+        // panic the launcher
+        let delay = Duration::try_from_secs_f64(*seconds)
+            .map_err(|_| format!("{} is not a number of seconds", seconds))?;
+
+        sleep(delay).await;
+
+        return Ok(());
+    }
+
+    tokio::time::timeout(timeout, poll_until_ready(condition))
+        .await
+        .map_err(|_| {
+            format!(
+                "not ready after {} seconds, {}",
+                timeout.as_secs(),
+                describe(condition)
+            )
+        })?
+}
+
+async fn poll_until_ready(condition: &ReadyCondition) -> Result<(), String> {
+    loop {
+        let ready = match condition {
+            ReadyCondition::Delay(_) => true,
+            ReadyCondition::File(path) => Path::new(&expand_path(path)?).exists(),
+            ReadyCondition::Port(port) => TcpStream::connect(("127.0.0.1", *port)).await.is_ok(),
+            ReadyCondition::Process(name) => {
+                let name = name.clone();
+
+                tokio::task::spawn_blocking(move || !find_processes(&name).is_empty())
+                    .await
+                    .unwrap_or(false)
+            }
+        };
+
+        if ready {
+            return Ok(());
+        }
+
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+pub fn describe(condition: &ReadyCondition) -> String {
+    match condition {
+        ReadyCondition::Delay(seconds) => format!("waiting {} seconds", seconds),
+        ReadyCondition::File(path) => format!("waiting for file {}", path),
+        ReadyCondition::Port(port) => format!("waiting for port {} to accept connections", port),
+        ReadyCondition::Process(name) => format!("waiting for process {}", name),
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    process::{Command, ExitStatus},
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+
+use tokio::{
+    process::Child,
+    time::{sleep, Instant},
+};
+
+use super::output::{spawn_logged, AppLog};
+use crate::{
+    config::{
+        data::{ProcessState, RestartOptions, RestartPolicy},
+        error::app_process_error::AppProcessError,
+    },
+    util::process::describe_exit_status,
+};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Waits for an application's process to exit, restarting it as its restart options say, until it
+/// exits for good or is asked to stop.
+pub async fn supervise(
+    name: &str,
+    command: &Command,
+    mut process: Child,
+    log: &Arc<AppLog>,
+    options: &RestartOptions,
+    state: &ProcessState,
+) -> Result<ExitStatus, AppProcessError> {
+    let mut attempts = 0;
+
+    loop {
+        let started_at = Instant::now();
+        let status = process
+            .wait()
+            .await
+            .map_err(|_| AppProcessError::AppWaitError)?;
+
+        state.pid.store(0, Ordering::Relaxed);
+
+        let should_restart = match options.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => true,
+        };
+
+        if state.stop.is_cancelled() || !should_restart {
+            return Ok(status);
+        }
+
+        if started_at.elapsed() >= Duration::from_secs(options.reset_after) {
+            attempts = 0;
+        }
+
+        if attempts >= options.max_attempts {
+            state.crash_looping.store(true, Ordering::Relaxed);
+
+            println!(
+                "{} {} after {} restarts in a row, it keeps crashing so it will not be restarted again",
+                name,
+                describe_exit_status(&status),
+                attempts
+            );
+
+            return Ok(status);
+        }
+
+        let delay = restart_delay(options.backoff, attempts);
+
+        attempts += 1;
+
+        println!(
+            "{} {}, restarting it in {:.1} seconds (attempt {} of {})",
+            name,
+            describe_exit_status(&status),
+            delay.as_secs_f64(),
+            attempts,
+            options.max_attempts
+        );
+
+        tokio::select! {
+            _ = sleep(delay) => (),
+            _ = state.stop.cancelled() => return Ok(status),
+        }
+
+        process = spawn_logged(command, log).map_err(|err| {
+            AppProcessError::AppLaunchError(format!("Failed to restart process: {}", err))
+        })?;
+
+        state
+            .pid
+            .store(process.id().unwrap_or(0), Ordering::Relaxed);
+        state.restarts.fetch_add(1, Ordering::Relaxed);
+
+        println!("Restarted {}", name);
+    }
+}
+
+/// How long to wait before restarting after `attempts` restarts in a row: the backoff, doubled for
+/// each of them, up to `MAX_BACKOFF`.
+fn restart_delay(backoff: f64, attempts: u32) -> Duration {
+    // 2^64 times any backoff over MAX_BACKOFF is already past it. The seconds are clamped before
+    // they become a Duration, which cannot hold every f64
+    let seconds = backoff.max(0.0) * 2f64.powi(attempts.min(64) as i32);
+
+    Duration::from_secs_f64(seconds.min(MAX_BACKOFF.as_secs_f64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let delays: Vec<f64> = (0..8)
+            .map(|attempts| restart_delay(1.0, attempts).as_secs_f64())
+            .collect();
+
+        assert_eq!(delays, [1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 60.0, 60.0]);
+        assert_eq!(restart_delay(0.5, 2), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn delay_never_overflows() {
+        assert_eq!(restart_delay(1e20, 0), MAX_BACKOFF);
+        assert_eq!(restart_delay(f64::MAX, 0), MAX_BACKOFF);
+        assert_eq!(restart_delay(1.0, 64), MAX_BACKOFF);
+        assert_eq!(restart_delay(1.0, u32::MAX), MAX_BACKOFF);
+        assert_eq!(restart_delay(0.0, u32::MAX), Duration::ZERO);
+        assert_eq!(restart_delay(-1.0, 3), Duration::ZERO);
+        assert_eq!(restart_delay(f64::NAN, 3), Duration::ZERO);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{path::Path, process::Command};
+
+use crate::{config::data::Runner, util::path::expand_path};
+
+/// A command that runs the executable with the runner. `~` and environment variables in the
+/// runner's paths and environment are expanded.
+pub fn runner_command(runner: &Runner, executable_path: &Path) -> Result<Command, String> {
+    match runner {
+        Runner::Native => Ok(Command::new(executable_path)),
+        Runner::Wine { binary, prefix } => {
+            let binary = expand_path(binary.as_deref().unwrap_or("wine"))?;
+            let mut command = Command::new(binary);
+
+            command.arg(executable_path);
+
+            if let Some(prefix) = prefix {
+                command.env("WINEPREFIX", expand_path(prefix)?);
+            }
+
+            Ok(command)
+        }
+        Runner::Custom {
+            command: program,
+            args,
+            env,
+        } => {
+            let mut command = Command::new(expand_path(program)?);
+
+            command.args(args).arg(executable_path);
+
+            for (name, value) in env {
+                command.env(name, expand_path(value)?);
+            }
+
+            Ok(command)
+        }
+    }
+}
+
+/// A path in the form the executable understands when run with the runner. Wine sees the root of
+/// the file system as the `Z:` drive.
+pub fn runner_path(runner: &Runner, path: &Path) -> String {
+    let path_str = path.to_string_lossy();
+
+    match runner {
+        Runner::Wine { .. } if path_str.starts_with('/') => {
+            format!("Z:{}", path_str.replace('/', "\\"))
+        }
+        _ => path_str.into_owned(),
+    }
+}
+
+pub fn describe(runner: &Runner) -> String {
+    match runner {
+        Runner::Native => "native".to_string(),
+        Runner::Wine { binary, prefix } => {
+            let mut description = format!("wine ({}", binary.as_deref().unwrap_or("wine"));
+
+            if let Some(prefix) = prefix {
+                description.push_str(&format!(", prefix {}", prefix));
+            }
+
+            description.push(')');
+            description
+        }
+        Runner::Custom { command, args, .. } => {
+            let mut description = command.clone();
+
+            for arg in args {
+                description.push(' ');
+                description.push_str(arg);
+            }
+
+            description
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, ffi::OsStr};
+
+    use super::*;
+
+    fn wine() -> Runner {
+        Runner::Wine {
+            binary: Some("/usr/bin/wine64".to_string()),
+            prefix: Some("/games/osu".to_string()),
+        }
+    }
+
+    #[test]
+    fn gives_wine_the_root_as_the_z_drive() {
+        assert_eq!(
+            runner_path(&wine(), Path::new("/home/peppy/replays/a.osr")),
+            "Z:\\home\\peppy\\replays\\a.osr"
+        );
+    }
+
+    #[test]
+    fn leaves_other_paths_alone() {
+        let custom = Runner::Custom {
+            command: "proton".to_string(),
+            args: vec![],
+            env: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            runner_path(&wine(), Path::new("replays/a.osr")),
+            "replays/a.osr"
+        );
+        assert_eq!(
+            runner_path(&Runner::Native, Path::new("/replays/a.osr")),
+            "/replays/a.osr"
+        );
+        assert_eq!(
+            runner_path(&custom, Path::new("/replays/a.osr")),
+            "/replays/a.osr"
+        );
+    }
+
+    #[test]
+    fn runs_the_executable_with_the_runner() {
+        let command = runner_command(&wine(), Path::new("/games/osu/osu!.exe")).unwrap();
+        let env: Vec<_> = command.get_envs().collect();
+
+        assert_eq!(command.get_program(), "/usr/bin/wine64");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["/games/osu/osu!.exe"]
+        );
+        assert_eq!(
+            env,
+            [(OsStr::new("WINEPREFIX"), Some(OsStr::new("/games/osu")))]
+        );
+
+        let custom = Runner::Custom {
+            command: "proton".to_string(),
+            args: vec!["run".to_string()],
+            env: BTreeMap::from([("STEAM_COMPAT_DATA_PATH".to_string(), "/pfx".to_string())]),
+        };
+        let command = runner_command(&custom, Path::new("osu!.exe")).unwrap();
+
+        assert_eq!(command.get_program(), "proton");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), ["run", "osu!.exe"]);
+        assert_eq!(command.get_envs().count(), 1);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{process::ExitStatus, sync::atomic::Ordering, time::Duration};
+
+use futures::{stream::FuturesUnordered, StreamExt};
+
+use super::signals::Signals;
+use crate::{
+    config::{
+        data::{AppProcess, ProcessState, ShutdownOptions},
+        error::app_process_error::AppProcessError,
+    },
+    util::process::{describe_exit_status, kill_process, terminate_process, Signal},
+};
+
+/// How an application ended.
+pub enum AppExit {
+    /// The application exited by itself.
+    Exited(Result<ExitStatus, AppProcessError>),
+    /// The application closed after the launcher asked it to.
+    Stopped,
+    /// The application did not close in time and was killed.
+    Killed,
+    /// The application could not be stopped.
+    StillRunning(String),
+}
+
+/// How an application ended, for the exit summary.
+pub struct AppSummary {
+    pub name: String,
+    pub exit: AppExit,
+    pub restarts: u32,
+    pub crash_looping: bool,
+    pub attached: bool,
+}
+
+impl AppSummary {
+    fn new(name: String, state: &ProcessState, exit: AppExit) -> AppSummary {
+        AppSummary {
+            name,
+            exit,
+            restarts: state.restarts.load(Ordering::Relaxed),
+            crash_looping: state.crash_looping.load(Ordering::Relaxed),
+            attached: state.attached.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Waits for the anchor application to exit, then stops the other applications in the reverse
+/// order they started. Without an anchor, waits for every application to exit by itself. When the
+/// launcher is asked to close, every application is stopped, passing the request on.
+pub async fn wait_and_shut_down(
+    mut processes: Vec<AppProcess>,
+    options: &ShutdownOptions,
+    signals: &mut Signals,
+) -> Vec<AppSummary> {
+    let anchor_position = options
+        .anchor
+        .as_ref()
+        .and_then(|anchor| processes.iter().position(|process| &process.name == anchor));
+
+    if let (Some(anchor), None) = (&options.anchor, anchor_position) {
+        println!(
+            "{} is not running, waiting for all applications to exit",
+            anchor
+        );
+    }
+
+    let mut results: Vec<Option<Result<ExitStatus, AppProcessError>>> =
+        processes.iter().map(|_| None).collect();
+
+    // Handles are only borrowed here, so the ones still running can be stopped afterwards
+    let signal = {
+        let mut running: FuturesUnordered<_> =
+            processes
+                .iter_mut()
+                .enumerate()
+                .map(|(position, process)| async move {
+                    (position, flatten((&mut process.handle).await))
+                })
+                .collect();
+
+        loop {
+            tokio::select! {
+                exited = running.next() => match exited {
+                    Some((position, result)) => {
+                        results[position] = Some(result);
+
+                        if Some(position) == anchor_position {
+                            break None;
+                        }
+                    }
+                    None => break None,
+                },
+                signal = signals.recv() => break Some(signal),
+            }
+        }
+    };
+
+    match (signal, anchor_position) {
+        (Some(signal), _) => println!("Received {}, stopping all applications...", signal),
+        (None, Some(anchor_position)) => println!(
+            "{} exited, stopping the other applications...",
+            processes[anchor_position].name
+        ),
+        (None, None) => (),
+    }
+
+    let timeout = Duration::from_secs(options.timeout);
+    let mut exits = vec![];
+
+    for (position, process) in processes.into_iter().enumerate().rev() {
+        let name = process.name.clone();
+        let state = process.state.clone();
+        let exit = match results[position].take() {
+            Some(result) => AppExit::Exited(result),
+            None => stop(process, timeout, signal.unwrap_or(Signal::Terminate)).await,
+        };
+        let summary = AppSummary::new(name, &state, exit);
+
+        // The anchor is listed first, as it is what closed the others
+        if Some(position) == anchor_position {
+            exits.insert(0, summary);
+        } else {
+            exits.push(summary);
+        }
+    }
+
+    exits
+}
+
+async fn stop(mut process: AppProcess, timeout: Duration, signal: Signal) -> AppExit {
+    if process.handle.is_finished() {
+        return AppExit::Exited(flatten(process.handle.await));
+    }
+
+    process.state.stop.cancel();
+
+    // The launcher stops the applications it runs itself when asked, the processes get the signal.
+    // Processes the launcher started are in their own process group, which gets it too
+    let whole_group = !process.state.attached.load(Ordering::Relaxed);
+
+    for pid in process_ids(&process.state) {
+        if let Err(error) = terminate_process(pid, signal, whole_group) {
+            println!("Could not ask {} to close: {}", process.name, error);
+        }
+    }
+
+    if tokio::time::timeout(timeout, &mut process.handle)
+        .await
+        .is_ok()
+    {
+        return AppExit::Stopped;
+    }
+
+    let pids = process_ids(&process.state);
+
+    if pids.is_empty() {
+        return AppExit::StillRunning(format!("did not stop within {} seconds", timeout.as_secs()));
+    }
+
+    println!(
+        "{} did not close within {} seconds, killing it",
+        process.name,
+        timeout.as_secs()
+    );
+
+    for pid in pids {
+        if let Err(error) = kill_process(pid, whole_group) {
+            return AppExit::StillRunning(format!("could not be killed: {}", error));
+        }
+    }
+
+    let _ = process.handle.await;
+
+    AppExit::Killed
+}
+
+/// The application's process, and the processes it had the launcher start for it.
+fn process_ids(state: &ProcessState) -> Vec<u32> {
+    let mut pids: Vec<u32> = state.helper_pids.lock().unwrap().clone();
+    let pid = state.pid.load(Ordering::Relaxed);
+
+    if pid != 0 {
+        pids.insert(0, pid);
+    }
+
+    pids
+}
+
+fn flatten(
+    result: Result<Result<ExitStatus, AppProcessError>, tokio::task::JoinError>,
+) -> Result<ExitStatus, AppProcessError> {
+    result.unwrap_or(Err(AppProcessError::AppWaitError))
+}
+
+pub fn print_summary(exits: &[AppSummary]) {
+    println!("Exit summary:");
+
+    for summary in exits {
+        let mut description = match &summary.exit {
+            // The exit code of a process the launcher did not start is not known
+            AppExit::Exited(Ok(_)) if summary.attached => "exited".to_string(),
+            AppExit::Exited(Ok(status)) => describe_exit_status(status),
+            AppExit::Exited(Err(error)) => error.to_string(),
+            AppExit::Stopped => "closed when asked".to_string(),
+            AppExit::Killed => "killed".to_string(),
+            AppExit::StillRunning(reason) => reason.clone(),
+        };
+
+        if summary.restarts > 0 {
+            description.push_str(&format!(", restarted {} times", summary.restarts));
+        }
+
+        if summary.crash_looping {
+            description.push_str(", kept crashing");
+        }
+
+        println!("  {}: {}", summary.name, description);
+    }
+}
@@ -0,0 +1,47 @@
+use std::io;
+
+use crate::util::process::Signal;
+
+/// The requests to close the launcher receives, listened for from when it is created, so a
+/// request during startup is handled once the applications have started.
+pub struct Signals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    interrupt: tokio::signal::windows::CtrlC,
+    #[cfg(windows)]
+    terminate: tokio::signal::windows::CtrlClose,
+}
+
+impl Signals {
+    pub fn listen() -> io::Result<Signals> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            Ok(Signals {
+                interrupt: signal(SignalKind::interrupt())?,
+                terminate: signal(SignalKind::terminate())?,
+            })
+        }
+
+        #[cfg(windows)]
+        {
+            use tokio::signal::windows::{ctrl_c, ctrl_close};
+
+            Ok(Signals {
+                interrupt: ctrl_c()?,
+                terminate: ctrl_close()?,
+            })
+        }
+    }
+
+    pub async fn recv(&mut self) -> Signal {
+        tokio::select! {
+            _ = self.interrupt.recv() => Signal::Interrupt,
+            _ = self.terminate.recv() => Signal::Terminate,
+        }
+    }
+}
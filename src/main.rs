@@ -1,54 +1,111 @@
+mod cli;
+mod commands;
 mod config;
+mod danser;
+mod launch;
 mod process;
+mod replay;
 mod util;
 
-use std::io::stdin;
+use std::io::{stdin, IsTerminal};
+use std::process::ExitCode;
 
+use clap::Parser;
+use cli::{Cli, Command};
 use config::manager::LauncherConfig;
-use config::traits::app_data::Application;
 use futures::future::join_all;
-use util::file::{delete_file, extract_zip, file_exists};
+use util::file::{delete_file, extract_zip};
 
 #[tokio::main]
-async fn main() {
-    let config_file_path = "./launcher_config.json";
-    let first_launch = !file_exists(config_file_path);
-    let launcher_config: LauncherConfig = config_file_path.into();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let config_file_path = cli
+        .config
+        .clone()
+        .unwrap_or_else(LauncherConfig::local_config_file_path);
+
+    if let Some(Command::Init(args)) = &cli.command {
+        return commands::init::run(args, &config_file_path);
+    }
 
-    if first_launch {
-        println!("First launch, please configure the launcher using the config file that was just created then run the launcher again.");
-        println!();
-        println!("Press enter to exit...");
+    if let Some(Command::Replay(args)) = &cli.command {
+        return commands::replay::run(args);
+    }
 
-        stdin().read_line(&mut String::new()).unwrap();
+    // A dry run only looks at the config, so it neither creates nor upgrades the config file
+    let dry_run = cli.dry_run && cli.command.is_none();
+    let first_launch = !dry_run && LauncherConfig::is_first_launch(&config_file_path);
+    let loaded_config = if dry_run {
+        LauncherConfig::read_only(&config_file_path)
+    } else {
+        LauncherConfig::try_from(config_file_path.as_str())
+    };
+
+    let mut launcher_config = match loaded_config {
+        Ok(launcher_config) => launcher_config,
+        Err(error) if cli.fallback_defaults => {
+            println!("{}", error);
+            println!("Falling back to the default config");
+
+            LauncherConfig::with_defaults(&config_file_path)
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Fix the config file or run with --fallback-defaults to launch with the default config");
 
-        return;
-    }
+            return ExitCode::FAILURE;
+        }
+    };
 
-    let mut download_futures = vec![];
+    if first_launch && cli.command.is_none() {
+        println!("First launch, please configure the launcher using the config file that was just created at {} then run the launcher again.", config_file_path);
+
+        // Only wait for the user when they started the launcher themselves, so scripts do not hang
+        if std::env::args().len() == 1 && stdin().is_terminal() {
+            println!();
+            println!("Press enter to exit...");
+
+            stdin().read_line(&mut String::new()).unwrap();
+        }
+
+        return ExitCode::SUCCESS;
+    }
 
-    if launcher_config.config.osu_trainer.can_download() {
-        println!("Downloading and extracting Osu! Trainer...");
+    if let Some(Command::Config { command }) = &cli.command {
+        return commands::config::run(command, &mut launcher_config);
+    }
 
-        let download = launcher_config.config.osu_trainer.download();
+    match launcher_config.apply_profile(cli.profile.as_deref()) {
+        // Only the plan is printed with --json, so it can be parsed
+        Ok(Some(profile_name)) if !cli.json => println!("Using profile: {}", profile_name),
+        Ok(_) => (),
+        Err(error) => {
+            eprintln!("{}", error);
 
-        download_futures.push(download);
+            return ExitCode::FAILURE;
+        }
     }
 
-    if launcher_config.config.danser.can_download() {
-        println!("Downloading and extracting Danser...");
+    if dry_run {
+        let plan = launch::dry_run::LaunchPlan::new(&launcher_config.config);
 
-        let download = launcher_config.config.danser.download();
+        if cli.json {
+            println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+        } else {
+            plan.print();
+        }
 
-        download_futures.push(download);
+        return ExitCode::SUCCESS;
     }
 
-    if launcher_config.config.open_tablet_driver.can_download() {
-        println!("Downloading and extracting OpenTabletDriver...");
+    let mut download_futures = vec![];
 
-        let download = launcher_config.config.open_tablet_driver.download();
+    for application in launcher_config.config.applications() {
+        if application.can_download() {
+            println!("Downloading and extracting {}...", application.get_name());
 
-        download_futures.push(download);
+            download_futures.push(application.download());
+        }
     }
 
     let download_path_results = join_all(download_futures).await;
@@ -58,11 +115,11 @@ async fn main() {
             Ok(zip_location) => {
                 let zip_location_str = zip_location.to_str().unwrap();
 
-                extract_zip(&zip_location_str)
-                    .expect(format!("Failed to extract {}", zip_location_str).as_str());
+                extract_zip(zip_location_str)
+                    .unwrap_or_else(|_| panic!("Failed to extract {}", zip_location_str));
 
-                delete_file(&zip_location_str)
-                    .expect(format!("Failed to delete {}", zip_location_str).as_str());
+                delete_file(zip_location_str)
+                    .unwrap_or_else(|_| panic!("Failed to delete {}", zip_location_str));
             }
             Err(error) => {
                 println!("Error downloading file: {}", error);
@@ -70,23 +127,34 @@ async fn main() {
         }
     }
 
-    let process_list: Vec<_> = [
-        &launcher_config.config.osu as &dyn Application,
-        &launcher_config.config.rewind as &dyn Application,
-        &launcher_config.config.danser as &dyn Application,
-        &launcher_config.config.open_tablet_driver as &dyn Application,
-        &launcher_config.config.osu_trainer as &dyn Application,
-    ]
-    .into_iter()
-    .filter_map(|config| match config.try_spawn_process() {
-        Ok(process) => Some(process),
+    // Listen before starting anything, so closing the launcher during startup still stops the
+    // applications it started
+    let mut signals = match launch::signals::Signals::listen() {
+        Ok(signals) => signals,
         Err(error) => {
-            println!("Error spawning process: {} - skipping", error);
+            eprintln!("Could not listen for Ctrl+C: {}", error);
 
-            None
+            return ExitCode::FAILURE;
         }
-    })
-    .collect();
+    };
+
+    let process_list = match launch::start_applications(&launcher_config.config).await {
+        Ok(process_list) => process_list,
+        Err(error) => {
+            eprintln!("{}", error);
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let exits = launch::shutdown::wait_and_shut_down(
+        process_list,
+        &launcher_config.config.shutdown,
+        &mut signals,
+    )
+    .await;
+
+    launch::shutdown::print_summary(&exits);
 
-    join_all(process_list).await;
+    ExitCode::SUCCESS
 }
@@ -1,72 +1,209 @@
-use std::{path::Path, process::Command};
+use std::{
+    collections::HashSet,
+    path::Path,
+    process::{Command, ExitStatus},
+    sync::Arc,
+};
 
 use crate::{
     config::{
-        data::{AppJoinHandle, DanserData},
+        data::{AppProcess, DanserData, LaunchContext, ProcessState, RenderTrigger},
         error::app_process_error::AppProcessError,
         traits::app_data::Application,
     },
-    util::win::is_async_key_pressed,
+    danser::{
+        naming::{unique_video_name, video_name, videos_dir},
+        queue::{print_jobs, RenderQueue},
+        settle::{wait_until_settled, written_replay},
+        trigger::{reads_replay, should_render, validate, Keyboard, TriggerDecision},
+    },
+    launch::{output::AppLog, runner::runner_path},
 };
-use notify::{Event, EventKind, RecursiveMode, Watcher};
+use notify::{Event, RecursiveMode, Watcher};
+use tokio::sync::mpsc::unbounded_channel;
 
-pub fn try_spawn_danser_process(app: &DanserData) -> Result<AppJoinHandle, AppProcessError> {
+pub fn try_spawn_danser_process(
+    app: &DanserData,
+    context: &LaunchContext,
+) -> Result<AppProcess, AppProcessError> {
     if !app.get_enabled() {
         return Err(AppProcessError::AppLaunchError(
             "Danser is not enabled".to_string(),
         ));
     }
 
-    let danser_executable_path = app.get_executable_path().unwrap();
-    let danser_settings_name = app.settings_name.clone().unwrap();
-    let replays_dir = app.get_replays_path().clone().unwrap();
-
-    if app.executable_exists() {
-        let watcher_task = tokio::task::spawn_blocking(move || {
-            let mut _watcher =
-                notify::recommended_watcher(move |res: Result<Event, _>| match res {
-                    Ok(event) => match event.kind {
-                        EventKind::Create(_) => {
-                            let full_path = event.paths[0].to_str().unwrap();
-                            let file_name =
-                                Path::new(&full_path).file_name().unwrap().to_str().unwrap();
-
-                            // if R key is held at this moment
-                            if is_async_key_pressed(0x52).unwrap_or(false) {
-                                println!("Rendering replay: {}", &file_name);
-
-                                Command::new(danser_executable_path.clone())
-                                    .arg(format!("--out={}", file_name))
-                                    .arg(format!("--settings={}", danser_settings_name))
-                                    .arg(format!("--replay={}", full_path))
-                                    .arg("--quickstart")
-                                    .spawn()
-                                    .expect("Failed to launch Danser");
-                            }
+    let danser = app.clone();
+    let context = context.clone();
+    let log = AppLog::open(
+        &app.get_name(),
+        &app.get_launch_options().log,
+        &context.launcher_dir,
+    );
+    let replays_dir = app
+        .get_replays_path()
+        .map_err(|error| AppProcessError::AppLaunchError(error.to_string()))?;
+
+    if !app.executable_exists() {
+        return Err(AppProcessError::AppLaunchError(
+            "Danser executable not found".to_string(),
+        ));
+    }
+
+    validate(&app.trigger).map_err(|error| {
+        AppProcessError::AppLaunchError(format!("Invalid danser.trigger: {}", error))
+    })?;
+
+    // Every render is given the settings, so a missing name stops Danser here instead of failing
+    // each render
+    let Some(settings_name) = app.settings_name.clone() else {
+        return Err(AppProcessError::AppLaunchError(
+            "danser.settings_name is not set, set it to the name of the Danser settings to render with"
+                .to_string(),
+        ));
+    };
+
+    if cfg!(not(windows)) && matches!(app.trigger, RenderTrigger::Hotkey { .. }) {
+        println!("Holding a key to render replays only works on Windows, change danser.trigger to render replays on this system");
+    }
+
+    // The watcher calls back on its own thread, the events are handled on the runtime
+    let (event_sender, mut events) = unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: Result<Event, notify::Error>| {
+        let _ = event_sender.send(event);
+    })
+    .map_err(|error| {
+        AppProcessError::AppLaunchError(format!("Error watching directory: {}", error))
+    })?;
+
+    watcher
+        .watch(&replays_dir, RecursiveMode::Recursive)
+        .map_err(|error| {
+            AppProcessError::AppLaunchError(format!("Error watching directory: {}", error))
+        })?;
+
+    println!("Watching directory: {:?}", replays_dir);
+
+    let state = Arc::new(ProcessState::default());
+    let watcher_state = state.clone();
+    let mut queue = RenderQueue::new(&app.render, log, state.clone());
+    let (settled_sender, mut settled) = unbounded_channel();
+    let mut settling = HashSet::new();
+    // Videos named this session, which may not have been written yet
+    let mut video_names = HashSet::new();
+
+    let watcher_task = tokio::spawn(async move {
+        // The watcher stops watching when it is dropped at the end of the task
+        let _watcher = watcher;
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(Ok(event)) => {
+                        let Some(replay) = written_replay(&event) else {
+                            continue;
+                        };
+
+                        // osu! writing a replay can be reported more than once, it is only waited
+                        // for once
+                        if settling.insert(replay.to_path_buf()) {
+                            // Keys and marker files are checked as the replay is saved, filters
+                            // once it is written
+                            let decision = (!reads_replay(&danser.trigger))
+                                .then(|| should_render(&danser.trigger, replay, &Keyboard));
+                            let replay = replay.to_path_buf();
+                            let settle_options = danser.settle.clone();
+                            let settled_sender = settled_sender.clone();
+
+                            tokio::spawn(async move {
+                                let result = wait_until_settled(&replay, &settle_options).await;
+                                let _ = settled_sender.send((replay, decision, result));
+                            });
                         }
-                        _ => (),
-                    },
-                    Err(e) => panic!("Error watching directory: {}", e),
-                });
+                    }
+                    Some(Err(error)) => println!("Error watching directory: {}", error),
+                    None => break,
+                },
+                Some((replay, decision, result)) = settled.recv() => {
+                    settling.remove(&replay);
 
-            let replays_dir = Path::new(&replays_dir);
+                    let decision = match result {
+                        Ok(()) => decision.unwrap_or_else(|| {
+                            should_render(&danser.trigger, &replay, &Keyboard)
+                        }),
+                        Err(reason) => TriggerDecision::Skip(reason),
+                    };
 
-            if let Ok(ref mut watcher_value) = _watcher {
-                match watcher_value.watch(replays_dir, RecursiveMode::Recursive) {
-                    Ok(_) => println!("Watching directory: {:?}", replays_dir),
-                    Err(e) => panic!("Error watching directory: {}", e),
+                    match decision {
+                        TriggerDecision::Render => {
+                            queue.submit(&replay, || {
+                                render_command(
+                                    &danser,
+                                    &settings_name,
+                                    &context,
+                                    &replay,
+                                    &mut video_names,
+                                )
+                            });
+                        }
+                        TriggerDecision::Skip(reason) => println!(
+                            "Not rendering replay {}: {}",
+                            replay.file_name().unwrap_or_default().to_string_lossy(),
+                            reason
+                        ),
+                    }
                 }
+                _ = watcher_state.stop.cancelled() => break,
             }
+        }
 
-            loop {
-                std::thread::sleep(std::time::Duration::from_secs(1));
-            }
-        });
+        // Renders were asked to stop along with the watcher, wait for them to finish
+        print_jobs(&queue.finish().await);
 
-        return Ok(watcher_task);
-    }
+        Ok(ExitStatus::default())
+    });
+
+    Ok(AppProcess {
+        name: app.get_name(),
+        state,
+        handle: watcher_task,
+    })
+}
+
+/// The command that renders the replay, to a video named from the output template that no other
+/// video has.
+fn render_command(
+    danser: &DanserData,
+    settings_name: &str,
+    context: &LaunchContext,
+    path: &Path,
+    video_names: &mut HashSet<String>,
+) -> Option<Command> {
+    // Danser's own args, env and cwd apply to every render
+    let mut command = match danser.command(context) {
+        Ok(command) => command,
+        Err(error) => {
+            println!("Error launching Danser: {}", error);
+
+            return None;
+        }
+    };
+
+    let video_name = unique_video_name(
+        video_name(&danser.output_template, path),
+        video_names,
+        &videos_dir(danser, settings_name),
+    );
+
+    video_names.insert(video_name.to_lowercase());
+
+    command
+        .arg(format!("--out={}", video_name))
+        .arg(format!("--settings={}", settings_name))
+        .arg(format!(
+            "--replay={}",
+            runner_path(danser.get_runner(context), path)
+        ))
+        .arg("--quickstart");
 
-    Err(AppProcessError::AppLaunchError(
-        "Danser executable not found".to_string(),
-    ))
+    Some(command)
 }
@@ -0,0 +1,475 @@
+pub mod mods;
+
+use std::{fmt, fs, path::Path};
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::config::error::replay_error::ReplayError;
+use mods::TARGET_PRACTICE;
+
+const TICKS_PER_SECOND: i64 = 10_000_000;
+// 1970-01-01 in .NET ticks
+const UNIX_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+/// The game mode a replay was played in.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameMode {
+    Osu,
+    Taiko,
+    Catch,
+    Mania,
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameMode::Osu => write!(f, "osu!"),
+            GameMode::Taiko => write!(f, "osu!taiko"),
+            GameMode::Catch => write!(f, "osu!catch"),
+            GameMode::Mania => write!(f, "osu!mania"),
+        }
+    }
+}
+
+/// The play an osu! replay (`.osr`) file describes.
+pub struct Replay {
+    pub mode: GameMode,
+    /// The version of osu! the replay was saved with, like `20240123`.
+    pub version: u32,
+    pub beatmap_md5: String,
+    pub player: String,
+    pub replay_md5: String,
+    pub count_300: u16,
+    pub count_100: u16,
+    pub count_50: u16,
+    pub count_geki: u16,
+    pub count_katu: u16,
+    pub count_miss: u16,
+    pub score: u32,
+    pub max_combo: u16,
+    /// Whether the play was a full combo.
+    pub perfect: bool,
+    /// The mods the play was made with, see [`mods`].
+    pub mods: u32,
+    /// The player's health over the play, as `time|health` pairs separated by commas.
+    pub life_bar: String,
+    /// When the play was made, in .NET ticks: 100 nanoseconds since 0001-01-01.
+    pub timestamp: i64,
+    /// The LZMA compressed cursor and key presses.
+    pub replay_data: Vec<u8>,
+    /// The ID of the score on the osu! website, 0 when it was not submitted.
+    pub online_score_id: i64,
+    /// The accuracy of plays with the Target Practice mod, which is saved separately.
+    pub target_practice_accuracy: Option<f64>,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Replay, ReplayError> {
+        let bytes = fs::read(path).map_err(|error| ReplayError::ReadFailed(error.to_string()))?;
+
+        Replay::parse(&bytes)
+    }
+
+    /// Parses a whole replay. Replays that end early, like ones osu! is still writing, fail with
+    /// [`ReplayError::UnexpectedEnd`].
+    pub fn parse(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = ReplayReader { bytes, position: 0 };
+
+        let mut replay = Replay {
+            mode: match reader.read_u8()? {
+                0 => GameMode::Osu,
+                1 => GameMode::Taiko,
+                2 => GameMode::Catch,
+                3 => GameMode::Mania,
+                mode => return Err(ReplayError::InvalidGameMode(mode)),
+            },
+            version: reader.read_u32()?,
+            beatmap_md5: reader.read_string()?,
+            player: reader.read_string()?,
+            replay_md5: reader.read_string()?,
+            count_300: reader.read_u16()?,
+            count_100: reader.read_u16()?,
+            count_50: reader.read_u16()?,
+            count_geki: reader.read_u16()?,
+            count_katu: reader.read_u16()?,
+            count_miss: reader.read_u16()?,
+            score: reader.read_u32()?,
+            max_combo: reader.read_u16()?,
+            perfect: reader.read_u8()? != 0,
+            mods: reader.read_u32()?,
+            life_bar: reader.read_string()?,
+            timestamp: reader.read_i64()?,
+            replay_data: {
+                let length_position = reader.position;
+                let length = usize::try_from(reader.read_i32()?).map_err(|_| {
+                    ReplayError::InvalidLength {
+                        position: length_position,
+                    }
+                })?;
+
+                reader.take(length)?.to_vec()
+            },
+            online_score_id: reader.read_i64()?,
+            target_practice_accuracy: None,
+        };
+
+        if replay.mods & TARGET_PRACTICE != 0 {
+            replay.target_practice_accuracy =
+                Some(f64::from_le_bytes(reader.take(8)?.try_into().unwrap()));
+        }
+
+        Ok(replay)
+    }
+
+    /// The accuracy of the play, from 0 to 1, worked out the way the game mode does.
+    pub fn accuracy(&self) -> f64 {
+        let [count_300, count_100, count_50, count_geki, count_katu, count_miss] = [
+            self.count_300,
+            self.count_100,
+            self.count_50,
+            self.count_geki,
+            self.count_katu,
+            self.count_miss,
+        ]
+        .map(f64::from);
+
+        let (points, max_points) = match self.mode {
+            GameMode::Osu => (
+                300.0 * count_300 + 100.0 * count_100 + 50.0 * count_50,
+                300.0 * (count_300 + count_100 + count_50 + count_miss),
+            ),
+            GameMode::Taiko => (
+                count_300 + 0.5 * count_100,
+                count_300 + count_100 + count_miss,
+            ),
+            GameMode::Catch => (
+                count_300 + count_100 + count_50,
+                count_300 + count_100 + count_50 + count_katu + count_miss,
+            ),
+            GameMode::Mania => (
+                300.0 * (count_geki + count_300)
+                    + 200.0 * count_katu
+                    + 100.0 * count_100
+                    + 50.0 * count_50,
+                300.0 * (count_geki + count_300 + count_katu + count_100 + count_50 + count_miss),
+            ),
+        };
+
+        if max_points == 0.0 {
+            return 1.0;
+        }
+
+        points / max_points
+    }
+
+    /// When the play was made, if the timestamp is a valid date.
+    pub fn played_at(&self) -> Option<DateTime<Utc>> {
+        let since_unix_epoch = self.timestamp.checked_sub(UNIX_EPOCH_TICKS)?;
+
+        DateTime::from_timestamp(
+            since_unix_epoch.div_euclid(TICKS_PER_SECOND),
+            (since_unix_epoch.rem_euclid(TICKS_PER_SECOND) * 100) as u32,
+        )
+    }
+}
+
+/// Reads the little-endian values replays are made of.
+struct ReplayReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ReplayReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], ReplayError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(ReplayError::UnexpectedEnd {
+                position: self.bytes.len(),
+            })?;
+        let taken = &self.bytes[self.position..end];
+
+        self.position = end;
+
+        Ok(taken)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, ReplayError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn read_i64(&mut self) -> Result<i64, ReplayError> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    // A string is either a 0 byte for no string, or 0x0b followed by its length in ULEB128 and
+    // its UTF-8 bytes
+    fn read_string(&mut self) -> Result<String, ReplayError> {
+        let start = self.position;
+
+        match self.read_u8()? {
+            0x00 => Ok(String::new()),
+            0x0b => {
+                let length = self.read_uleb128()?;
+                let bytes = self.take(length)?;
+
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|_| ReplayError::InvalidString { position: start })
+            }
+            _ => Err(ReplayError::InvalidString { position: start }),
+        }
+    }
+
+    fn read_uleb128(&mut self) -> Result<usize, ReplayError> {
+        let start = self.position;
+        let mut value: usize = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+
+            if shift >= usize::BITS {
+                return Err(ReplayError::InvalidString { position: start });
+            }
+
+            value |= ((byte & 0x7f) as usize) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+
+            shift += 7;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // 2024-01-04T21:20:00.1234567Z
+    const TIMESTAMP: i64 = UNIX_EPOCH_TICKS + 1_704_403_200 * TICKS_PER_SECOND + 1_234_567;
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut bytes = vec![0x0b];
+        let mut length = value.len();
+
+        loop {
+            let byte = (length & 0x7f) as u8;
+
+            length >>= 7;
+
+            if length == 0 {
+                bytes.push(byte);
+                break;
+            }
+
+            bytes.push(byte | 0x80);
+        }
+
+        bytes.extend_from_slice(value.as_bytes());
+        bytes
+    }
+
+    // A replay with a different value in every field, written out the way osu! saves it
+    pub(crate) fn fixture(mods: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+
+        bytes.push(0); // osu!
+        bytes.extend_from_slice(&20240123u32.to_le_bytes());
+        bytes.extend(string("0123456789abcdef0123456789abcdef"));
+        bytes.extend(string("peppy"));
+        bytes.extend(string("fedcba9876543210fedcba9876543210"));
+        for count in [100u16, 10, 2, 7, 3, 1] {
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.extend_from_slice(&1_234_567u32.to_le_bytes());
+        bytes.extend_from_slice(&321u16.to_le_bytes());
+        bytes.push(0); // not a full combo
+        bytes.extend_from_slice(&mods.to_le_bytes());
+        // Longer than 127 bytes, so its length takes two bytes
+        bytes.extend(string(&"1000|1,".repeat(20)));
+        bytes.extend_from_slice(&TIMESTAMP.to_le_bytes());
+        bytes.extend_from_slice(&4i32.to_le_bytes());
+        bytes.extend_from_slice(&[0x5d, 0x00, 0x00, 0x20]);
+        bytes.extend_from_slice(&4_567_890_123i64.to_le_bytes());
+
+        if mods & TARGET_PRACTICE != 0 {
+            bytes.extend_from_slice(&0.875f64.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    fn replay_with_counts(mode: GameMode, counts: [u16; 6]) -> Replay {
+        let mut replay = Replay::parse(&fixture(0)).unwrap();
+        let [count_300, count_100, count_50, count_geki, count_katu, count_miss] = counts;
+
+        replay.mode = mode;
+        replay.count_300 = count_300;
+        replay.count_100 = count_100;
+        replay.count_50 = count_50;
+        replay.count_geki = count_geki;
+        replay.count_katu = count_katu;
+        replay.count_miss = count_miss;
+
+        replay
+    }
+
+    #[test]
+    fn parses_every_field() {
+        let replay = Replay::parse(&fixture(72)).unwrap();
+
+        assert_eq!(replay.mode, GameMode::Osu);
+        assert_eq!(replay.version, 20240123);
+        assert_eq!(replay.beatmap_md5, "0123456789abcdef0123456789abcdef");
+        assert_eq!(replay.player, "peppy");
+        assert_eq!(replay.replay_md5, "fedcba9876543210fedcba9876543210");
+        assert_eq!(replay.count_300, 100);
+        assert_eq!(replay.count_100, 10);
+        assert_eq!(replay.count_50, 2);
+        assert_eq!(replay.count_geki, 7);
+        assert_eq!(replay.count_katu, 3);
+        assert_eq!(replay.count_miss, 1);
+        assert_eq!(replay.score, 1_234_567);
+        assert_eq!(replay.max_combo, 321);
+        assert!(!replay.perfect);
+        assert_eq!(replay.mods, 72);
+        assert_eq!(replay.life_bar, "1000|1,".repeat(20));
+        assert_eq!(replay.timestamp, TIMESTAMP);
+        assert_eq!(replay.replay_data, [0x5d, 0x00, 0x00, 0x20]);
+        assert_eq!(replay.online_score_id, 4_567_890_123);
+        assert_eq!(replay.target_practice_accuracy, None);
+    }
+
+    #[test]
+    fn parses_target_practice_accuracy() {
+        let replay = Replay::parse(&fixture(TARGET_PRACTICE)).unwrap();
+
+        assert_eq!(replay.target_practice_accuracy, Some(0.875));
+    }
+
+    #[test]
+    fn parses_empty_strings() {
+        let mut bytes = vec![3];
+
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0, 0]);
+        bytes.extend_from_slice(&[0; 12 + 4 + 2 + 1 + 4]);
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; 8 + 4 + 8]);
+
+        let replay = Replay::parse(&bytes).unwrap();
+
+        assert_eq!(replay.mode, GameMode::Mania);
+        assert_eq!(replay.player, "");
+        assert!(replay.replay_data.is_empty());
+    }
+
+    #[test]
+    fn every_truncated_replay_fails() {
+        for mods in [0, TARGET_PRACTICE] {
+            let bytes = fixture(mods);
+
+            for length in 0..bytes.len() {
+                assert!(
+                    matches!(
+                        Replay::parse(&bytes[..length]),
+                        Err(ReplayError::UnexpectedEnd { .. })
+                    ),
+                    "a replay cut off after {} of {} bytes was parsed",
+                    length,
+                    bytes.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let mut bytes = fixture(0);
+
+        bytes[0] = 4;
+        assert!(matches!(
+            Replay::parse(&bytes),
+            Err(ReplayError::InvalidGameMode(4))
+        ));
+
+        let mut bytes = fixture(0);
+
+        // The beatmap MD5 starts after the mode and version
+        bytes[5] = 0x0c;
+        assert!(matches!(
+            Replay::parse(&bytes),
+            Err(ReplayError::InvalidString { position: 5 })
+        ));
+
+        let mut bytes = fixture(0);
+        let length_position = bytes.len() - 8 - 4 - 4;
+
+        bytes[length_position..length_position + 4].copy_from_slice(&(-1i32).to_le_bytes());
+        assert!(matches!(
+            Replay::parse(&bytes),
+            Err(ReplayError::InvalidLength { position }) if position == length_position
+        ));
+    }
+
+    #[test]
+    fn accuracy_per_game_mode() {
+        let cases = [
+            // 300s, 100s, 50s, gekis, katus, misses
+            (GameMode::Osu, [100, 10, 2, 7, 3, 1], 31100.0 / 33900.0),
+            (GameMode::Taiko, [90, 10, 0, 0, 0, 0], 0.95),
+            (GameMode::Catch, [50, 5, 40, 0, 5, 0], 0.95),
+            (GameMode::Mania, [20, 2, 1, 10, 5, 2], 10250.0 / 12000.0),
+            (GameMode::Osu, [0; 6], 1.0),
+        ];
+
+        for (mode, counts, expected) in cases {
+            let accuracy = replay_with_counts(mode, counts).accuracy();
+
+            assert!(
+                (accuracy - expected).abs() < 1e-9,
+                "{} accuracy {} is not {}",
+                mode,
+                accuracy,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn played_at_converts_ticks() {
+        let mut replay = Replay::parse(&fixture(0)).unwrap();
+
+        assert_eq!(
+            replay.played_at().map(|played_at| played_at.to_rfc3339()),
+            Some("2024-01-04T21:20:00.123456700+00:00".to_string())
+        );
+
+        replay.timestamp = 0;
+        assert_eq!(
+            replay.played_at().map(|played_at| played_at.to_rfc3339()),
+            Some("0001-01-01T00:00:00+00:00".to_string())
+        );
+
+        replay.timestamp = i64::MIN;
+        assert_eq!(replay.played_at(), None);
+    }
+}
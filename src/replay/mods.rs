@@ -0,0 +1,63 @@
+pub const TARGET_PRACTICE: u32 = 1 << 23;
+
+/// The mods a replay can be played with, by the bit that is set for them in the replay and the
+/// acronym osu! shows for them.
+pub const MODS: [(u32, &str); 31] = [
+    (1, "NF"),
+    (1 << 1, "EZ"),
+    (1 << 2, "TD"),
+    (1 << 3, "HD"),
+    (1 << 4, "HR"),
+    (1 << 5, "SD"),
+    (1 << 6, "DT"),
+    (1 << 7, "RX"),
+    (1 << 8, "HT"),
+    (1 << 9, "NC"),
+    (1 << 10, "FL"),
+    (1 << 11, "AT"),
+    (1 << 12, "SO"),
+    (1 << 13, "AP"),
+    (1 << 14, "PF"),
+    (1 << 15, "4K"),
+    (1 << 16, "5K"),
+    (1 << 17, "6K"),
+    (1 << 18, "7K"),
+    (1 << 19, "8K"),
+    (1 << 20, "FI"),
+    (1 << 21, "RD"),
+    (1 << 22, "CN"),
+    (TARGET_PRACTICE, "TP"),
+    (1 << 24, "9K"),
+    (1 << 25, "CO"),
+    (1 << 26, "1K"),
+    (1 << 27, "3K"),
+    (1 << 28, "2K"),
+    (1 << 29, "V2"),
+    (1 << 30, "MR"),
+];
+
+/// The bit of a mod, by its acronym in any case.
+pub fn mod_bit(acronym: &str) -> Option<u32> {
+    MODS.iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(acronym))
+        .map(|(bit, _)| *bit)
+}
+
+/// The acronyms of the mods, in the order osu! lists them. Mods that come with another one, like
+/// DT with NC, are left out.
+pub fn acronyms(mods: u32) -> Vec<&'static str> {
+    let mut implied = 0;
+
+    if mods & mod_bit("NC").unwrap() != 0 {
+        implied |= mod_bit("DT").unwrap();
+    }
+
+    if mods & mod_bit("PF").unwrap() != 0 {
+        implied |= mod_bit("SD").unwrap();
+    }
+
+    MODS.iter()
+        .filter(|(bit, _)| mods & bit != 0 && implied & bit == 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
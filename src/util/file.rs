@@ -1,6 +1,6 @@
 use std::{
     fs::File,
-    io::{copy, BufReader},
+    io::{copy, BufReader, Write},
     path::Path,
 };
 
@@ -45,7 +45,7 @@ pub fn extract_zip(zip_path: &str) -> std::io::Result<()> {
         let outpath = parent_dir.join(file.name());
 
         // Check if the file is a directory
-        if (&*file.name()).ends_with('/') {
+        if file.name().ends_with('/') {
             // If it is, create the directory and continue
             std::fs::create_dir_all(&outpath)?;
         } else {
@@ -53,7 +53,7 @@ pub fn extract_zip(zip_path: &str) -> std::io::Result<()> {
             if let Some(p) = Path::new(&outpath).parent() {
                 // If the parent doesn't exist create it
                 if !p.exists() {
-                    std::fs::create_dir_all(&p)?;
+                    std::fs::create_dir_all(p)?;
                 }
             }
 
@@ -69,3 +69,184 @@ pub fn extract_zip(zip_path: &str) -> std::io::Result<()> {
 pub fn delete_file(path: &str) -> std::io::Result<()> {
     std::fs::remove_file(path)
 }
+
+/// Writes a file by writing a temporary file next to it and renaming it into place, so readers
+/// never see a partially written file.
+pub fn write_atomically(path: &str, contents: &str) -> std::io::Result<()> {
+    let temp_path = format!("{}.{}.tmp", path, uuid::Uuid::new_v4());
+    let mut temp_file = File::create(&temp_path)?;
+
+    let result = temp_file
+        .write_all(contents.as_bytes())
+        .and_then(|_| temp_file.sync_all())
+        .and_then(|_| std::fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Copies a file to `<path>.bak` before it is replaced, shifting older backups to `<path>.1.bak`,
+/// `<path>.2.bak` and so on, up to `count` backups.
+pub fn rotate_backups(path: &str, count: usize) -> std::io::Result<()> {
+    let backup_path = |index: usize| match index {
+        0 => format!("{}.bak", path),
+        index => format!("{}.{}.bak", path, index),
+    };
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    for index in (0..count - 1).rev() {
+        if file_exists(&backup_path(index)) {
+            std::fs::rename(backup_path(index), backup_path(index + 1))?;
+        }
+    }
+
+    std::fs::copy(path, backup_path(0))?;
+
+    Ok(())
+}
+
+// Names Windows does not allow for files, with or without an extension
+const RESERVED_FILE_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+const MAX_FILE_NAME_LENGTH: usize = 200;
+
+/// Makes a name usable as a file name on any system. Characters that are not allowed in file
+/// names are replaced with `_`, and names Windows reserves or does not allow are changed.
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows drops trailing dots and spaces, which would change the name
+    sanitized = sanitized
+        .trim_end_matches(['.', ' '])
+        .trim_start()
+        .to_string();
+
+    if sanitized.chars().count() > MAX_FILE_NAME_LENGTH {
+        sanitized = sanitized.chars().take(MAX_FILE_NAME_LENGTH).collect();
+    }
+
+    let stem = sanitized.split('.').next().unwrap_or_default();
+
+    if RESERVED_FILE_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        sanitized.insert(0, '_');
+    }
+
+    if sanitized.is_empty() {
+        sanitized.push('_');
+    }
+
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("launcher_config.json");
+        let path = path.to_str().unwrap();
+
+        for version in 1..=5 {
+            if file_exists(path) {
+                rotate_backups(path, 3).unwrap();
+            }
+
+            write_atomically(path, &format!("version {}", version)).unwrap();
+        }
+
+        let read = |suffix: &str| fs::read_to_string(format!("{}{}", path, suffix)).ok();
+
+        assert_eq!(read("").as_deref(), Some("version 5"));
+        assert_eq!(read(".bak").as_deref(), Some("version 4"));
+        assert_eq!(read(".1.bak").as_deref(), Some("version 3"));
+        assert_eq!(read(".2.bak").as_deref(), Some("version 2"));
+        assert_eq!(read(".3.bak"), None);
+    }
+
+    #[test]
+    fn keeps_no_backups_when_asked_for_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("launcher_config.json");
+        let path = path.to_str().unwrap();
+
+        write_atomically(path, "version 1").unwrap();
+        rotate_backups(path, 0).unwrap();
+
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn replaces_files_whole_without_leaving_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("launcher_config.json");
+        let path = path.to_str().unwrap();
+
+        write_atomically(path, "a longer first version").unwrap();
+        write_atomically(path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn failed_writes_leave_the_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("launcher_config.json");
+
+        // A directory cannot be replaced by a file
+        fs::create_dir(&path).unwrap();
+
+        assert!(write_atomically(path.to_str().unwrap(), "contents").is_err());
+        assert!(path.is_dir());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        let cases = [
+            ("peppy HDDT 98.50%", "peppy HDDT 98.50%"),
+            ("a<b>c:d\"e/f\\g|h?i*j", "a_b_c_d_e_f_g_h_i_j"),
+            ("tab\there", "tab_here"),
+            ("  trailing dots... ", "trailing dots"),
+            ("con", "_con"),
+            ("CON.mp4", "_CON.mp4"),
+            ("lpt1 .txt", "_lpt1 .txt"),
+            ("COM10", "COM10"),
+            ("console", "console"),
+            ("", "_"),
+            ("...", "_"),
+        ];
+
+        for (name, sanitized) in cases {
+            assert_eq!(sanitize_file_name(name), sanitized, "{:?}", name);
+        }
+
+        assert_eq!(
+            sanitize_file_name(&"é".repeat(MAX_FILE_NAME_LENGTH + 10)),
+            "é".repeat(MAX_FILE_NAME_LENGTH)
+        );
+    }
+}
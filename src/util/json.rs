@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+
+use serde_json::{value::RawValue, Map, Value};
+
+/// Deep-merges `overrides` into `target`, with values from `overrides` winning. Objects are merged
+/// key by key. Arrays of objects with a `name` are merged entry by entry, matching on `name`, so a
+/// single custom app can be overridden without repeating the whole list. Any other array, including
+/// an empty one, replaces the array it overrides.
+pub fn merge_overrides(target: &mut Value, overrides: &Value) {
+    match (target, overrides) {
+        (Value::Object(target_map), Value::Object(overrides_map)) => {
+            for (key, override_value) in overrides_map {
+                match target_map.get_mut(key) {
+                    Some(target_value) => merge_overrides(target_value, override_value),
+                    None => {
+                        target_map.insert(key.clone(), override_value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Array(target_list), Value::Array(overrides_list))
+            if !overrides_list.is_empty()
+                && overrides_list.iter().all(|item| entry_name(item).is_some()) =>
+        {
+            for override_item in overrides_list {
+                let existing_item = target_list
+                    .iter_mut()
+                    .find(|item| entry_name(item) == entry_name(override_item));
+
+                match existing_item {
+                    Some(target_item) => merge_overrides(target_item, override_item),
+                    None => target_list.push(override_item.clone()),
+                }
+            }
+        }
+        (target, overrides) => *target = overrides.clone(),
+    }
+}
+
+/// Lists every setting in a document as a dotted path and its value, like `danser.enabled`.
+/// Entries of named arrays use their name as the path segment, like `custom_apps.tosu.enabled`.
+pub fn flatten(value: &Value) -> Vec<(String, &Value)> {
+    let mut settings = vec![];
+
+    flatten_into(value, String::new(), &mut settings);
+
+    settings
+}
+
+fn flatten_into<'v>(value: &'v Value, prefix: String, settings: &mut Vec<(String, &'v Value)>) {
+    let join = |segment: &str| {
+        if prefix.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{}", prefix, segment)
+        }
+    };
+
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                flatten_into(item, join(key), settings);
+            }
+        }
+        Value::Array(list)
+            if !list.is_empty() && list.iter().all(|item| entry_name(item).is_some()) =>
+        {
+            for item in list {
+                flatten_into(item, join(entry_name(item).unwrap()), settings);
+            }
+        }
+        _ => settings.push((prefix, value)),
+    }
+}
+
+/// Looks up a setting by its path segments. Array entries are matched by name, then by index.
+pub fn get_path<'v>(value: &'v Value, path: &[&str]) -> Option<&'v Value> {
+    path.iter().try_fold(value, |value, segment| match value {
+        Value::Object(map) => map.get(*segment),
+        Value::Array(list) => list
+            .iter()
+            .find(|item| entry_name(item) == Some(segment))
+            .or_else(|| list.get(segment.parse::<usize>().ok()?)),
+        _ => None,
+    })
+}
+
+/// Sets a setting by its path segments, creating any missing objects along the way. A segment that
+/// does not match an entry of a named array adds a new entry with that name.
+pub fn set_path(target: &mut Value, path: &[&str], new_value: Value) {
+    let Some((segment, rest)) = path.split_first() else {
+        *target = new_value;
+
+        return;
+    };
+
+    if let Value::Array(list) = target {
+        let position = list
+            .iter()
+            .position(|item| entry_name(item) == Some(segment))
+            .or_else(|| {
+                segment
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index < list.len())
+            });
+
+        let index = match position {
+            Some(index) => index,
+            None => {
+                let mut entry = Map::new();
+
+                entry.insert("name".to_string(), Value::from(*segment));
+                list.push(Value::Object(entry));
+
+                list.len() - 1
+            }
+        };
+
+        return set_path(&mut list[index], rest, new_value);
+    }
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    let child = target
+        .as_object_mut()
+        .unwrap()
+        .entry(segment.to_string())
+        .or_insert(Value::Null);
+
+    set_path(child, rest, new_value);
+}
+
+/// Finds the 1-based line and column where a setting's value starts in a JSON document's text.
+pub fn locate(text: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let mut raw: &RawValue = serde_json::from_str(text).ok()?;
+
+    for segment in path {
+        let json = raw.get();
+
+        raw = if json.starts_with('{') {
+            let map: BTreeMap<String, &RawValue> = serde_json::from_str(json).ok()?;
+
+            *map.get(*segment)?
+        } else if json.starts_with('[') {
+            let list: Vec<&RawValue> = serde_json::from_str(json).ok()?;
+            let named_item = list.iter().find(|item| {
+                serde_json::from_str::<Value>(item.get())
+                    .is_ok_and(|item| entry_name(&item) == Some(segment))
+            });
+
+            match named_item {
+                Some(item) => *item,
+                None => *list.get(segment.parse::<usize>().ok()?)?,
+            }
+        } else {
+            return None;
+        };
+    }
+
+    // Raw values borrow from the original text, so their position is the distance between them
+    let offset = raw.get().as_ptr() as usize - text.as_ptr() as usize;
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |index| index + 1)..]
+        .chars()
+        .count()
+        + 1;
+
+    Some((line, column))
+}
+
+fn entry_name(value: &Value) -> Option<&str> {
+    value.get("name").and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merges_objects_key_by_key() {
+        let mut target = json!({"osu": {"enabled": true, "path": "osu!"}, "runner": "native"});
+
+        merge_overrides(&mut target, &json!({"osu": {"enabled": false}}));
+
+        assert_eq!(
+            target,
+            json!({"osu": {"enabled": false, "path": "osu!"}, "runner": "native"})
+        );
+    }
+
+    #[test]
+    fn merges_named_arrays_by_name() {
+        let mut target = json!({"custom_apps": [
+            {"name": "tosu", "enabled": true, "args": ["--port", "24050"]},
+            {"name": "overlay", "enabled": true},
+        ]});
+
+        merge_overrides(
+            &mut target,
+            &json!({"custom_apps": [
+                {"name": "overlay", "enabled": false},
+                {"name": "recorder", "enabled": true},
+            ]}),
+        );
+
+        assert_eq!(
+            target,
+            json!({"custom_apps": [
+                {"name": "tosu", "enabled": true, "args": ["--port", "24050"]},
+                {"name": "overlay", "enabled": false},
+                {"name": "recorder", "enabled": true},
+            ]})
+        );
+    }
+
+    #[test]
+    fn replaces_unnamed_arrays() {
+        let mut target = json!({"args": ["--port", "24050"], "depends_on": ["tosu"]});
+
+        merge_overrides(&mut target, &json!({"args": ["--quiet"]}));
+
+        assert_eq!(target, json!({"args": ["--quiet"], "depends_on": ["tosu"]}));
+    }
+
+    #[test]
+    fn empty_arrays_replace_what_they_override() {
+        let mut target = json!({
+            "depends_on": ["tosu"],
+            "custom_apps": [{"name": "tosu", "enabled": true}],
+        });
+
+        merge_overrides(&mut target, &json!({"depends_on": [], "custom_apps": []}));
+
+        assert_eq!(target, json!({"depends_on": [], "custom_apps": []}));
+    }
+
+    #[test]
+    fn flattens_settings_into_paths() {
+        let document = json!({
+            "danser": {"enabled": true, "render": {"max_parallel": 2}},
+            "osu": {"args": ["-devserver"], "env": {}},
+            "custom_apps": [{"name": "tosu", "enabled": false}],
+        });
+
+        let settings: Vec<(String, Value)> = flatten(&document)
+            .into_iter()
+            .map(|(path, value)| (path, value.clone()))
+            .collect();
+
+        assert_eq!(
+            settings,
+            [
+                ("danser.enabled".to_string(), json!(true)),
+                ("danser.render.max_parallel".to_string(), json!(2)),
+                ("osu.args".to_string(), json!(["-devserver"])),
+                ("osu.env".to_string(), json!({})),
+                ("custom_apps.tosu.name".to_string(), json!("tosu")),
+                ("custom_apps.tosu.enabled".to_string(), json!(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn locates_settings_in_the_text() {
+        let text = r#"{
+  "danser": {
+    "enabled": true
+  },
+  "custom_apps": [
+    { "name": "tosu", "enabled": false },
+    { "name": "overlay", "enabled": true }
+  ]
+}"#;
+
+        assert_eq!(locate(text, &[]), Some((1, 1)));
+        assert_eq!(locate(text, &["danser", "enabled"]), Some((3, 16)));
+        assert_eq!(
+            locate(text, &["custom_apps", "overlay", "enabled"]),
+            Some((7, 37))
+        );
+        assert_eq!(locate(text, &["custom_apps", "0", "name"]), Some((6, 15)));
+        assert_eq!(locate(text, &["danser", "settings_name"]), None);
+        assert_eq!(locate("{ not json", &["danser"]), None);
+    }
+}
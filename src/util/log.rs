@@ -0,0 +1,138 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// A log file that is moved aside to `<name>.1.log`, `<name>.2.log`... when it grows past
+/// `max_size` bytes, keeping `max_files` old files.
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: u32,
+}
+
+impl LogFile {
+    pub fn open(path: &Path, max_size: u64, max_files: u32) -> io::Result<LogFile> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let length = line.len() as u64 + 1;
+
+        if self.size > 0 && self.size + length > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += length;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+
+        if self.max_files > 0 {
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.path.with_file_name(format!("{}.{}.log", stem, index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: PathBuf) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn moves_full_logs_aside_and_keeps_the_last_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        let path = logs.join("osu.log");
+        // Two 6-byte lines fit in one file
+        let mut log = LogFile::open(&path, 12, 2).unwrap();
+
+        for line in [
+            "line1", "line2", "line3", "line4", "line5", "line6", "line7",
+        ] {
+            log.write_line(line).unwrap();
+        }
+
+        assert_eq!(read(path.clone()), "line7\n");
+        assert_eq!(read(logs.join("osu.1.log")), "line5\nline6\n");
+        assert_eq!(read(logs.join("osu.2.log")), "line3\nline4\n");
+        assert!(!logs.join("osu.3.log").exists());
+    }
+
+    #[test]
+    fn counts_what_the_log_already_holds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("osu.log");
+
+        fs::write(&path, "line1\n").unwrap();
+
+        let mut log = LogFile::open(&path, 12, 1).unwrap();
+
+        log.write_line("line2").unwrap();
+        log.write_line("line3").unwrap();
+
+        assert_eq!(read(path), "line3\n");
+        assert_eq!(read(dir.path().join("osu.1.log")), "line1\nline2\n");
+    }
+
+    #[test]
+    fn writes_lines_longer_than_the_limit_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("osu.log");
+        let mut log = LogFile::open(&path, 4, 0).unwrap();
+
+        log.write_line("a long line").unwrap();
+
+        assert_eq!(read(path.clone()), "a long line\n");
+
+        // Without old files to keep, a full log starts over
+        log.write_line("next").unwrap();
+
+        assert_eq!(read(path), "next\n");
+        assert!(!dir.path().join("osu.1.log").exists());
+    }
+}
@@ -1,2 +1,6 @@
 pub mod file;
+pub mod json;
+pub mod log;
+pub mod path;
+pub mod process;
 pub mod win;
@@ -0,0 +1,195 @@
+use std::{env, path::PathBuf};
+
+/// The directory the launcher's executable is in, or the working directory if it cannot be found.
+pub fn launcher_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Replaces `{name}` placeholders in a value. Text in braces that is not one of the placeholders is
+/// left as it is. Fails when a placeholder is used that has no value.
+pub fn replace_placeholders(
+    value: &str,
+    placeholders: &[(&str, Option<&str>)],
+) -> Result<String, String> {
+    let mut replaced = value.to_string();
+
+    for (name, placeholder_value) in placeholders {
+        let placeholder = format!("{{{}}}", name);
+
+        if !replaced.contains(&placeholder) {
+            continue;
+        }
+
+        match placeholder_value {
+            Some(placeholder_value) => replaced = replaced.replace(&placeholder, placeholder_value),
+            None => return Err(format!("{} in \"{}\" has no value", placeholder, value)),
+        }
+    }
+
+    Ok(replaced)
+}
+
+/// Expands `~` at the start of a path, and `$VAR` or `${VAR}` anywhere in it, using the
+/// environment. `$$` is a literal `$`. Fails with a message naming the variable when a variable is
+/// not set, rather than expanding it to nothing.
+pub fn expand_path(path: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        expanded.push_str(&home_dir()?);
+        rest = &rest[1..];
+    }
+
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+
+            continue;
+        }
+
+        let (name, after) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| format!("Missing closing brace in path \"{}\"", path))?;
+
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+
+            (&rest[..end], &rest[end..])
+        };
+
+        if name.is_empty() {
+            return Err(format!(
+                "Missing variable name after $ in path \"{}\"",
+                path
+            ));
+        }
+
+        expanded.push_str(&variable(name, path)?);
+        rest = after;
+    }
+
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+fn variable(name: &str, path: &str) -> Result<String, String> {
+    match env::var(name) {
+        Ok(value) => Ok(value),
+        // HOME is rarely set on Windows, so fall back to the user's profile directory
+        Err(_) if name == "HOME" => home_dir(),
+        Err(_) => Err(format!(
+            "Environment variable {} used in path \"{}\" is not set",
+            name, path
+        )),
+    }
+}
+
+fn home_dir() -> Result<String, String> {
+    dirs::home_dir()
+        .map(|home| home.to_string_lossy().into_owned())
+        .ok_or_else(|| "Could not find the home directory".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each test sets its own variables, as tests run at the same time
+    #[test]
+    fn expands_variables() {
+        env::set_var("OSU_LAUNCHER_TEST_GAMES", "/games");
+        env::set_var("OSU_LAUNCHER_TEST_OSU", "osu!");
+
+        assert_eq!(
+            expand_path("${OSU_LAUNCHER_TEST_GAMES}/${OSU_LAUNCHER_TEST_OSU}/Replays"),
+            Ok("/games/osu!/Replays".to_string())
+        );
+        assert_eq!(
+            expand_path("$OSU_LAUNCHER_TEST_GAMES/$OSU_LAUNCHER_TEST_OSU.exe"),
+            Ok("/games/osu!.exe".to_string())
+        );
+        assert_eq!(
+            expand_path("$OSU_LAUNCHER_TEST_GAMES$$OSU_LAUNCHER_TEST_OSU"),
+            Ok("/games$OSU_LAUNCHER_TEST_OSU".to_string())
+        );
+        assert_eq!(
+            expand_path("C:\\Games\\osu!"),
+            Ok("C:\\Games\\osu!".to_string())
+        );
+    }
+
+    #[test]
+    fn expands_the_home_directory() {
+        let home = home_dir().unwrap();
+        let home_variable = env::var("HOME").unwrap_or_else(|_| home.clone());
+
+        assert_eq!(expand_path("~"), Ok(home.clone()));
+        assert_eq!(expand_path("~/osu!"), Ok(format!("{}/osu!", home)));
+        assert_eq!(
+            expand_path("$HOME/osu!"),
+            Ok(format!("{}/osu!", home_variable))
+        );
+        // Only a ~ at the start is the home directory
+        assert_eq!(expand_path("osu!/~"), Ok("osu!/~".to_string()));
+        assert_eq!(expand_path("~osu"), Ok("~osu".to_string()));
+    }
+
+    #[test]
+    fn fails_on_unknown_variables() {
+        env::remove_var("OSU_LAUNCHER_TEST_UNSET");
+
+        assert_eq!(
+            expand_path("${OSU_LAUNCHER_TEST_UNSET}/osu!"),
+            Err(
+                "Environment variable OSU_LAUNCHER_TEST_UNSET used in path \"${OSU_LAUNCHER_TEST_UNSET}/osu!\" is not set"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            expand_path("${OSU_LAUNCHER_TEST_UNSET"),
+            Err("Missing closing brace in path \"${OSU_LAUNCHER_TEST_UNSET\"".to_string())
+        );
+        assert_eq!(
+            expand_path("osu!/$/Replays"),
+            Err("Missing variable name after $ in path \"osu!/$/Replays\"".to_string())
+        );
+    }
+
+    #[test]
+    fn replaces_placeholders() {
+        let placeholders = [
+            ("app_path", Some("/opt/tosu")),
+            ("osu_path", Some("/games/osu!")),
+            ("launcher_dir", None),
+        ];
+
+        assert_eq!(
+            replace_placeholders(
+                "--songs={osu_path}/Songs --config={app_path}/{app_path}.json",
+                &placeholders
+            ),
+            Ok("--songs=/games/osu!/Songs --config=/opt/tosu//opt/tosu.json".to_string())
+        );
+        assert_eq!(
+            replace_placeholders("{unknown} {osu_path", &placeholders),
+            Ok("{unknown} {osu_path".to_string())
+        );
+        assert_eq!(
+            replace_placeholders("{launcher_dir}/logs", &placeholders),
+            Err("{launcher_dir} in \"{launcher_dir}/logs\" has no value".to_string())
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+};
+
+pub struct RunningProcess {
+    pub pid: u32,
+    pub name: String,
+    /// The full path of the process's executable, when it can be read.
+    pub path: Option<PathBuf>,
+}
+
+/// Whether a process name matches an executable name, ignoring case and a `.exe` extension.
+pub fn process_name_matches(process_name: &str, executable_name: &str) -> bool {
+    let trim = |name: &str| {
+        let name = name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(name)
+            .to_lowercase();
+
+        match name.strip_suffix(".exe") {
+            Some(stem) => stem.to_string(),
+            None => name,
+        }
+    };
+
+    trim(process_name) == trim(executable_name)
+}
+
+pub fn find_processes(executable_name: &str) -> Vec<RunningProcess> {
+    running_processes()
+        .into_iter()
+        .filter(|process| process_name_matches(&process.name, executable_name))
+        .collect()
+}
+
+/// The running copies of an executable. Processes are compared by the full path of their
+/// executable, so an unrelated program with the same name does not count. Processes whose path
+/// cannot be read, like programs run through Wine, are compared by name.
+pub fn find_executable_processes(executable_path: &Path) -> Vec<RunningProcess> {
+    running_processes()
+        .into_iter()
+        .filter(|process| match &process.path {
+            Some(path) => same_path(path, executable_path),
+            None => process_name_matches(&process.name, &executable_path.to_string_lossy()),
+        })
+        .collect()
+}
+
+fn same_path(path: &Path, other: &Path) -> bool {
+    let canonical = |path: &Path| {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        // Windows paths are not case sensitive
+        if cfg!(windows) {
+            PathBuf::from(path.to_string_lossy().to_lowercase())
+        } else {
+            path
+        }
+    };
+
+    canonical(path) == canonical(other)
+}
+
+/// Lists the running processes by reading `/proc`.
+#[cfg(target_os = "linux")]
+pub fn running_processes() -> Vec<RunningProcess> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+
+            // Only the numbered directories are processes
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+
+            if is_zombie(&entry.path()) {
+                return None;
+            }
+
+            // The command line has the full executable name, also for programs run through Wine,
+            // where `comm` is cut off at 15 characters
+            let command_line = std::fs::read(entry.path().join("cmdline")).unwrap_or_default();
+            let executable = command_line.split(|byte| *byte == 0).next().unwrap_or(&[]);
+
+            let name = if executable.is_empty() {
+                std::fs::read_to_string(entry.path().join("comm"))
+                    .ok()?
+                    .trim_end()
+                    .to_string()
+            } else {
+                String::from_utf8_lossy(executable).into_owned()
+            };
+
+            // The executable of a program run through Wine is Wine's loader, its command line has
+            // the Windows path of the program instead
+            let path = if is_windows_path(&name) {
+                None
+            } else {
+                std::fs::read_link(entry.path().join("exe")).ok()
+            };
+
+            Some(RunningProcess { pid, name, path })
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+
+    path.contains('\\') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Whether a process has exited but was not cleaned up by its parent yet, so it only looks running.
+#[cfg(target_os = "linux")]
+fn is_zombie(process_dir: &std::path::Path) -> bool {
+    // The state comes after the executable name in parentheses, which can contain spaces
+    std::fs::read_to_string(process_dir.join("stat"))
+        .ok()
+        .and_then(|stat| {
+            stat.rsplit_once(')')
+                .map(|(_, rest)| rest.trim_start().starts_with('Z'))
+        })
+        .unwrap_or(false)
+}
+
+/// Lists the running processes with `tasklist`.
+#[cfg(windows)]
+pub fn running_processes() -> Vec<RunningProcess> {
+    let Ok(output) = Command::new("tasklist")
+        .args(["/FO", "CSV", "/NH"])
+        .output()
+    else {
+        return vec![];
+    };
+
+    // Each line looks like "osu!.exe","1234","Console","1","123,456 K"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split("\",\"");
+            let name = fields.next()?.trim_start_matches('"').to_string();
+            let pid = fields.next()?.parse().ok()?;
+
+            Some(RunningProcess {
+                pid,
+                name,
+                path: process_path(pid),
+            })
+        })
+        .collect()
+}
+
+/// The full path of a process's executable. Processes of other users, and system processes, can
+/// not be read.
+#[cfg(windows)]
+fn process_path(pid: u32) -> Option<PathBuf> {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+    use winapi::um::{
+        handleapi::CloseHandle, processthreadsapi::OpenProcess,
+        winbase::QueryFullProcessImageNameW, winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+
+        if process.is_null() {
+            return None;
+        }
+
+        // Long enough for any path, including ones starting with \\?\
+        let mut path = vec![0u16; 32768];
+        let mut length = path.len() as u32;
+        let read = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut length);
+
+        CloseHandle(process);
+
+        (read != 0).then(|| PathBuf::from(OsString::from_wide(&path[..length as usize])))
+    }
+}
+
+/// Lists the running processes with `ps`.
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn running_processes() -> Vec<RunningProcess> {
+    let Ok(output) = Command::new("ps").args(["-axo", "pid=,comm="]).output() else {
+        return vec![];
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, name) = line.trim_start().split_once(' ')?;
+
+            Some(RunningProcess {
+                pid: pid.parse().ok()?,
+                name: name.trim().to_string(),
+                path: None,
+            })
+        })
+        .collect()
+}
+
+/// Whether a process with this id is running.
+pub fn process_exists(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let process_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+
+        process_dir.exists() && !is_zombie(&process_dir)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    running_processes().iter().any(|process| process.pid == pid)
+}
+
+/// A copy of a command, to run it again.
+pub fn clone_command(command: &Command) -> Command {
+    let mut clone = Command::new(command.get_program());
+
+    clone.args(command.get_args());
+
+    for (name, value) in command.get_envs() {
+        match value {
+            Some(value) => clone.env(name, value),
+            None => clone.env_remove(name),
+        };
+    }
+
+    if let Some(current_dir) = command.get_current_dir() {
+        clone.current_dir(current_dir);
+    }
+
+    clone
+}
+
+pub fn describe_exit_status(status: &ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exited with code {}", code),
+        None => "was terminated".to_string(),
+    }
+}
+
+/// A request to close that the launcher received, and passes on to the applications.
+#[derive(Clone, Copy)]
+pub enum Signal {
+    /// Ctrl+C, or `SIGINT`.
+    Interrupt,
+    /// `SIGTERM`, or the console window being closed on Windows.
+    Terminate,
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::Interrupt => write!(f, "Ctrl+C"),
+            Signal::Terminate => write!(f, "a request to close"),
+        }
+    }
+}
+
+/// Asks a process to close, like closing its window. With `whole_group`, the processes it started
+/// are asked too.
+pub fn terminate_process(pid: u32, signal: Signal, whole_group: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    let mut command = {
+        let _ = signal;
+        let mut command = Command::new("taskkill");
+        if whole_group {
+            command.arg("/T");
+        }
+        command.args(["/PID", &pid.to_string()]);
+        command
+    };
+
+    #[cfg(not(windows))]
+    let mut command = kill_command(
+        match signal {
+            Signal::Interrupt => "-INT",
+            Signal::Terminate => "-TERM",
+        },
+        pid,
+        whole_group,
+    );
+
+    run_quietly(&mut command)
+}
+
+/// Kills a process, without giving it a chance to close. With `whole_group`, the processes it
+/// started are killed too.
+pub fn kill_process(pid: u32, whole_group: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("taskkill");
+        command.arg("/F");
+        if whole_group {
+            command.arg("/T");
+        }
+        command.args(["/PID", &pid.to_string()]);
+        command
+    };
+
+    #[cfg(not(windows))]
+    let mut command = kill_command("-KILL", pid, whole_group);
+
+    run_quietly(&mut command)
+}
+
+#[cfg(not(windows))]
+fn kill_command(signal: &str, pid: u32, whole_group: bool) -> Command {
+    let mut command = Command::new("kill");
+
+    // A negative id sends the signal to the process group the launcher put the process in
+    if whole_group {
+        command.args([signal, "--", &format!("-{}", pid)]);
+    } else {
+        command.args([signal, &pid.to_string()]);
+    }
+
+    command
+}
+
+/// Makes a command start in its own process group, so signals to the launcher's console do not
+/// reach it before the launcher passes them on, and so it does not outlive the launcher on Linux.
+pub fn isolate_child(command: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+
+        command.process_group(0);
+
+        #[cfg(target_os = "linux")]
+        // SAFETY: prctl is async-signal-safe, so it can run between fork and exec
+        unsafe {
+            command.pre_exec(|| {
+                // The child is stopped when the thread that started it exits. Applications are
+                // first started on the main thread, which runs the launch in `block_on`, and
+                // restarts and Danser renders on the runtime's worker threads. Both last until the
+                // runtime shuts down as the launcher exits. The blocking pool's threads exit when
+                // idle, so children must not be started from `spawn_blocking`
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = command;
+}
+
+/// Puts a started process in a job object that is closed when the launcher exits, so Windows
+/// kills the process if the launcher crashes.
+#[cfg(windows)]
+pub fn add_to_launcher_job(process: &tokio::process::Child) -> Result<(), String> {
+    use std::{ptr, sync::OnceLock};
+    use winapi::um::{
+        jobapi2::{AssignProcessToJobObject, CreateJobObjectW, SetInformationJobObject},
+        winnt::{
+            JobObjectExtendedLimitInformation, HANDLE, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        },
+    };
+
+    struct Job(HANDLE);
+
+    // The handle is only used to add processes to the job, which is thread safe
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    static JOB: OnceLock<Option<Job>> = OnceLock::new();
+
+    let job = JOB.get_or_init(|| unsafe {
+        let job = CreateJobObjectW(ptr::null_mut(), ptr::null());
+
+        if job.is_null() {
+            return None;
+        }
+
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+        let set = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &mut limits as *mut _ as *mut _,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        );
+
+        // The job handle is never closed, Windows closes it when the launcher exits
+        (set != 0).then_some(Job(job))
+    });
+
+    let Some(job) = job else {
+        return Err(std::io::Error::last_os_error().to_string());
+    };
+
+    let Some(handle) = process.raw_handle() else {
+        return Ok(());
+    };
+
+    if unsafe { AssignProcessToJobObject(job.0, handle as HANDLE) } == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok(())
+}
+
+fn run_quietly(command: &mut Command) -> Result<(), String> {
+    let output = command.output().map_err(|error| error.to_string())?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
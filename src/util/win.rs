@@ -1,16 +1,23 @@
-pub use dirs::*;
+#[cfg(windows)]
 use winapi::um::winuser;
 
 pub fn is_async_key_pressed(key: i32) -> Result<bool, String> {
-    if key < 0 || key > 255 {
+    if !(0..=255).contains(&key) {
         return Err("Invalid key code".to_string()); // Invalid key code
     }
 
-    let result = unsafe { winuser::GetAsyncKeyState(key) };
+    #[cfg(windows)]
+    {
+        let result = unsafe { winuser::GetAsyncKeyState(key) };
 
-    if result == 0 {
-        return Ok(false);
+        if result == 0 {
+            return Ok(false);
+        }
+
+        Ok(result < 0) // The most significant bit is set if the key is down
     }
 
-    Ok(result < 0) // The most significant bit is set if the key is down
+    // Reading the keyboard state needs the Windows API, so keys never count as held elsewhere
+    #[cfg(not(windows))]
+    Ok(false)
 }